@@ -2,14 +2,25 @@ use vai_core as core;
 
 use crate::{Error, Result};
 
+const DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(150);
+
 fn prompt(executor: Option<&core::executors::Executor>) -> impl rucline::prompt::Builder {
     use rucline::crossterm::style::Colorize;
     if let Some(executor) = executor {
-        rucline::prompt::Prompt::from(format!(
-            "{}::{}> ",
-            "vai".green(),
-            executor.name().dark_green()
-        ))
+        if executor.is_offline() {
+            rucline::prompt::Prompt::from(format!(
+                "{}::{}{}> ",
+                "vai".green(),
+                executor.name().dark_green(),
+                "(offline)".dark_grey()
+            ))
+        } else {
+            rucline::prompt::Prompt::from(format!(
+                "{}::{}> ",
+                "vai".green(),
+                executor.name().dark_green()
+            ))
+        }
     } else {
         rucline::prompt::Prompt::from(format!("{}> ", "vai".green()))
     }
@@ -34,6 +45,24 @@ fn extract_trailing_buffer(buffer: &str) -> rucline::Buffer {
     })
 }
 
+/// Executes `query`, opening the direct URL instead if `query` is one of the `offered`
+/// suggestions and carries one
+fn run(
+    executor: &core::executors::Executor,
+    query: &str,
+    offered: &[core::suggestion::Suggestion],
+) -> Result {
+    match offered
+        .iter()
+        .rev()
+        .find(|suggestion| suggestion.text() == query && suggestion.url().is_some())
+    {
+        Some(suggestion) => executor.execute_suggestion(suggestion),
+        None => executor.execute(query),
+    }
+    .map_err(Error::Core)
+}
+
 fn interactive_prompt(
     mut buffer: rucline::Buffer,
     executors: &core::executors::Executors,
@@ -54,25 +83,73 @@ fn interactive_prompt(
             let trimmed = accepted.trim_start();
             if let Some(executor) = trimmed.split(' ').next().and_then(|t| executors.find(t)) {
                 let history = executor.history().unwrap_or_else(|_| Vec::new());
+                let fetcher = executor.fetcher(crate::SUGGESTION_COUNT, crate::ranking(), DEBOUNCE);
+                let trailing_buffer = extract_trailing_buffer(trimmed);
+                fetcher.request(&trailing_buffer);
+                // Every remote suggestion shown, so that the one chosen can be executed as such
+                let offered = std::cell::RefCell::new(Vec::new());
+
+                // Every edit requests suggestions in the background, so they are likely ready
+                // by the time they are asked for
+                let completion = |b: &rucline::Buffer| -> Option<String> {
+                    if b.is_empty() {
+                        return None;
+                    }
+
+                    fetcher.request(b);
+                    history
+                        .iter()
+                        .map(String::as_str)
+                        .find(|entry| entry.starts_with(b.as_str()))
+                        .map(|entry| String::from(&entry[b.len()..]))
+                        .or_else(|| {
+                            let suggestions = fetcher.get(b)?;
+                            let suggestion = suggestions
+                                .suggestions()
+                                .iter()
+                                .find(|suggestion| suggestion.text().starts_with(b.as_str()))?;
+                            offered.borrow_mut().push(suggestion.clone());
+                            Some(String::from(&suggestion.text()[b.len()..]))
+                        })
+                };
 
-                let susggestions = |b: &rucline::Buffer| -> Vec<std::borrow::Cow<'_, str>> {
-                    executor
-                        .suggest(b)
-                        .unwrap_or_else(|_| Vec::new())
-                        .into_iter()
-                        .map(std::convert::Into::into)
-                        .collect()
+                // Never waits for the network: until the remote suggestions for the buffer arrive,
+                // only the history is offered. The prompt only redraws on key presses, so
+                // suggestions fetched meanwhile show up on the next one, such as `Tab`
+                let suggestions = |b: &rucline::Buffer| -> Vec<std::borrow::Cow<'_, str>> {
+                    fetcher.request(b);
+                    fetcher.get(b).map_or_else(
+                        || {
+                            executor
+                                .history_suggestions(b, crate::SUGGESTION_COUNT)
+                                .unwrap_or_else(|_| Vec::new())
+                                .into_iter()
+                                .map(core::suggestion::Suggestion::into_text)
+                                .map(std::convert::Into::into)
+                                .collect()
+                        },
+                        |suggestions| {
+                            offered
+                                .borrow_mut()
+                                .extend(suggestions.suggestions().iter().cloned());
+                            suggestions
+                                .suggestions()
+                                .iter()
+                                .map(|suggestion| String::from(suggestion.text()).into())
+                                .collect()
+                        },
+                    )
                 };
 
                 match prompt(Some(executor))
-                    .buffer(extract_trailing_buffer(trimmed))
-                    .completer(history)
-                    .suggester_fn(susggestions)
+                    .buffer(trailing_buffer)
+                    .completer_fn(completion)
+                    .suggester_fn(suggestions)
                     .erase_after_read(true)
                     .read_line()?
                 {
                     rucline::Outcome::Accepted(accepted) => {
-                        return executor.execute(&accepted).map_err(Error::Core);
+                        return run(executor, &accepted, &offered.borrow());
                     }
                     rucline::Outcome::Canceled(canceled) => {
                         buffer = format!("{} {}", executor.name(), canceled).into();
@@ -92,9 +169,11 @@ pub(super) fn execute(args: Vec<String>) -> Result {
 
     if !args.is_empty() {
         if let Some(executor) = executors.find(&args[0]) {
-            return executor
-                .execute(&args.into_iter().skip(1).collect::<Vec<_>>().join(" "))
-                .map_err(Error::Core);
+            return run(
+                executor,
+                &args.into_iter().skip(1).collect::<Vec<_>>().join(" "),
+                &[],
+            );
         }
     }
 
@@ -4,8 +4,14 @@ pub(super) enum Flag {
     Help,
     Write,
     Read,
+    Check,
+    Diff,
+    Schema,
+    Config,
     Targets,
     Suggest,
+    ClearCache,
+    Offline,
     Unknown(String),
 }
 
@@ -14,8 +20,14 @@ impl Flag {
         match self {
             Flag::Write => "-w",
             Flag::Read => "-r",
+            Flag::Check => "-c",
+            Flag::Diff => "-d",
+            Flag::Schema => "-j",
+            Flag::Config => "-C",
             Flag::Targets => "-t",
             Flag::Suggest => "-s",
+            Flag::ClearCache => "-x",
+            Flag::Offline => "-o",
             Flag::Help => "-h",
             Flag::Unknown(_) => "",
         }
@@ -25,8 +37,14 @@ impl Flag {
         match self {
             Flag::Write => "--write",
             Flag::Read => "--read",
+            Flag::Check => "--check",
+            Flag::Diff => "--diff",
+            Flag::Schema => "--schema",
+            Flag::Config => "--config",
             Flag::Targets => "--targets",
             Flag::Suggest => "--suggest",
+            Flag::ClearCache => "--clear-cache",
+            Flag::Offline => "--offline",
             Flag::Help => "--help",
             Flag::Unknown(_) => "",
         }
@@ -34,10 +52,20 @@ impl Flag {
 
     fn description(&self) -> &'static str {
         match self {
-            Flag::Write => "Write saved configuration to stdout",
-            Flag::Read => "Read configuration from stdin and save",
+            Flag::Write => "Write saved configuration to stdout [--json|--toml|--yaml]",
+            Flag::Read => {
+                "Read JSON, TOML or YAML configuration from stdin and save [--replace|--merge|--add-only|--interactive] [--dry-run]"
+            }
+            Flag::Check => "Validate configuration from stdin without saving",
+            Flag::Diff => "Compare saved configuration with a file or stdin [<file>] [--json]",
+            Flag::Schema => "Write the JSON Schema of the configuration to stdout",
+            Flag::Config => {
+                "List configuration backups [backups], roll back to one [restore <id>] or show where targets come from [targets]"
+            }
             Flag::Targets => "Write configured targets to stdout",
             Flag::Suggest => "Print a list of suggestions for the given input",
+            Flag::ClearCache => "Remove all cached suggestions",
+            Flag::Offline => "Run the remaining arguments without using the network",
             Flag::Help => "Display usage message",
             Flag::Unknown(_) => "",
         }
@@ -47,8 +75,14 @@ impl Flag {
         vec![
             Flag::Write,
             Flag::Read,
+            Flag::Check,
+            Flag::Diff,
+            Flag::Schema,
+            Flag::Config,
             Flag::Targets,
             Flag::Suggest,
+            Flag::ClearCache,
+            Flag::Offline,
             Flag::Help,
         ]
     }
@@ -60,8 +94,14 @@ impl std::convert::From<&str> for Flag {
             "-h" | "--help" => Flag::Help,
             "-r" | "--read" => Flag::Read,
             "-w" | "--write" => Flag::Write,
+            "-c" | "--check" => Flag::Check,
+            "-d" | "--diff" => Flag::Diff,
+            "-j" | "--schema" => Flag::Schema,
+            "-C" | "--config" => Flag::Config,
             "-t" | "--targets" => Flag::Targets,
             "-s" | "--suggest" => Flag::Suggest,
+            "-x" | "--clear-cache" => Flag::ClearCache,
+            "-o" | "--offline" => Flag::Offline,
             command => Flag::Unknown(String::from(command)),
         }
     }
@@ -71,22 +111,22 @@ pub(super) fn print_usage() {
     let name = application_name();
 
     println!("Usage:");
-    println!("    {} [target] [query]", name);
-    println!("    {} <option>", name);
+    println!("    {name} [target] [query]");
+    println!("    {name} <option>");
     println!();
 
     println!("Arguments:");
     println!(
-        "    target          Which target to query{}",
+        "    target              Which target to query{}",
         list_targets()
     );
-    println!("    query           Query string for <target>");
+    println!("    query               Query string for <target>");
     println!();
 
     println!("Options:");
     for flag in Flag::values() {
         println!(
-            "    {}, {:<12}{}",
+            "    {}, {:<16}{}",
             flag.short(),
             flag.long(),
             flag.description()
@@ -10,9 +10,19 @@ mod support;
 
 type Result<T = ()> = std::result::Result<T, Error>;
 
+const SUGGESTION_COUNT: usize = 20;
+
+fn ranking() -> core::suggestion::Ranking {
+    std::env::var("VAI_RANKING")
+        .ok()
+        .and_then(|ranking| ranking.parse().ok())
+        .unwrap_or_default()
+}
+
 #[derive(Debug)]
 enum Error {
     NoQuery,
+    NoBackup,
     UnknownTarget,
     UnknownCommand(String),
     Core(core::error::Error),
@@ -37,10 +47,11 @@ impl std::fmt::Display for Error {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         match self {
             Error::NoQuery => write!(fmt, "No query specified"),
+            Error::NoBackup => write!(fmt, "No backup specified"),
             Error::UnknownTarget => write!(fmt, "Unrecognized target"),
-            Error::UnknownCommand(command) => write!(fmt, "Unrecognized command: {}", command),
-            Error::Core(err) => write!(fmt, "{}", err),
-            Error::Rucline(err) => write!(fmt, "{}", err),
+            Error::UnknownCommand(command) => write!(fmt, "Unrecognized command: {command}"),
+            Error::Core(err) => write!(fmt, "{err}"),
+            Error::Rucline(err) => write!(fmt, "{err}"),
         }
     }
 }
@@ -58,6 +69,10 @@ fn select_mode<I: std::iter::Iterator<Item = String>>(input: I) -> Mode {
         .filter(|s| !s.is_empty())
         .collect::<Vec<_>>();
 
+    mode(args)
+}
+
+fn mode(args: Vec<String>) -> Mode {
     if !args.is_empty() && args[0].starts_with('-') {
         Mode::Support(args)
     } else {
@@ -65,13 +80,16 @@ fn select_mode<I: std::iter::Iterator<Item = String>>(input: I) -> Mode {
     }
 }
 
-fn main() {
-    match select_mode(std::env::args()) {
+fn run(mode: Mode) -> Result {
+    match mode {
         Mode::Support(args) => support::support(args),
         Mode::Execute(args) => executor::execute(args),
     }
-    .unwrap_or_else(|err| {
-        eprintln!("{}", err);
+}
+
+fn main() {
+    run(select_mode(std::env::args())).unwrap_or_else(|err| {
+        eprintln!("{err}");
         std::process::exit(-1);
     });
 }
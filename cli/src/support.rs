@@ -9,13 +9,21 @@ pub(super) fn support(args: Vec<String>) -> Result {
             flag::print_usage();
             Ok(())
         }
-        flag::Flag::Write => core::executors::load_default()?
-            .to_json()
-            .map(|json| println!("{}", json))
+        flag::Flag::Write => write(&args[1..]),
+        flag::Flag::Read => import(&args[1..]),
+        flag::Flag::Check => core::executors::load_from_stdin_extending(&load_bases()?)
+            .map(|executors| {
+                println!(
+                    "Configuration is valid, with {} targets",
+                    executors.list_targets().len()
+                );
+            })
             .map_err(Error::from),
-        flag::Flag::Read => core::executors::load_from_stdin()?
-            .save_default()
+        flag::Flag::Diff => diff(&args[1..]),
+        flag::Flag::Schema => core::executors::json_schema()
+            .map(|schema| println!("{schema}"))
             .map_err(Error::from),
+        flag::Flag::Config => config(&args[1..]),
         flag::Flag::Targets => print_targets(),
         flag::Flag::Suggest => {
             if args.len() < 2 {
@@ -23,21 +31,15 @@ pub(super) fn support(args: Vec<String>) -> Result {
             } else {
                 let executors = core::executors::load_default()?;
                 if let Some(target) = executors.find(&args[1]) {
-                    let query = match extract_query(args, 2) {
-                        Ok(query) => query,
-                        Err(_) => return Ok(()),
+                    let Ok(query) = extract_query(args, 2) else {
+                        return Ok(());
                     };
 
                     target
-                        .suggest(&query)
-                        .unwrap_or_else(|_| vec![])
-                        .into_iter()
-                        .for_each(|entry| println!("{}", entry));
-                    target
-                        .fuzzy_history(&query, 10)
-                        .unwrap_or_else(|_| vec![])
-                        .into_iter()
-                        .for_each(|entry| println!("{}", entry));
+                        .ranked_suggestions(&query, crate::SUGGESTION_COUNT, crate::ranking())?
+                        .suggestions()
+                        .iter()
+                        .for_each(|entry| println!("{}", entry.text()));
                     Ok(())
                 } else {
                     let possible_targets = executors
@@ -49,22 +51,195 @@ pub(super) fn support(args: Vec<String>) -> Result {
                         Err(Error::UnknownTarget)
                     } else {
                         for possible_target in possible_targets {
-                            println!("{}", possible_target);
+                            println!("{possible_target}");
                         }
                         Ok(())
                     }
                 }
             }
         }
+        flag::Flag::ClearCache => core::cache::clear().map_err(Error::from),
+        flag::Flag::Offline => {
+            core::http::set_offline(true);
+            crate::run(crate::mode(args.into_iter().skip(1).collect()))
+        }
         flag::Flag::Unknown(command) => Err(Error::UnknownCommand(command)),
     }
 }
 
+fn write(options: &[String]) -> Result {
+    let format = match options {
+        [] => core::format::Format::Json,
+        [format] => format
+            .strip_prefix("--")
+            .and_then(|format| format.parse().ok())
+            .ok_or_else(|| Error::UnknownCommand(format.clone()))?,
+        [_, unknown, ..] => return Err(Error::UnknownCommand(unknown.clone())),
+    };
+
+    core::executors::load_saved()?
+        .to_format(format)
+        .map(|config| println!("{}", config.trim_end()))
+        .map_err(Error::from)
+}
+
+/// How `-r` combines the configuration from stdin with the saved one
+enum Import {
+    Mode(core::import::Mode),
+    Interactive,
+}
+
+fn import(options: &[String]) -> Result {
+    let mut mode = Import::Mode(core::import::Mode::Replace);
+    let mut dry_run = false;
+    for option in options {
+        match option.as_str() {
+            "--replace" => mode = Import::Mode(core::import::Mode::Replace),
+            "--merge" => mode = Import::Mode(core::import::Mode::Merge),
+            "--add-only" => mode = Import::Mode(core::import::Mode::AddOnly),
+            "--interactive" => mode = Import::Interactive,
+            "--dry-run" => dry_run = true,
+            unknown => return Err(Error::UnknownCommand(String::from(unknown))),
+        }
+    }
+
+    let incoming = core::executors::load_from_stdin_extending(&load_bases()?)?;
+    let current = load_current()?;
+
+    let import = match mode {
+        Import::Mode(mode) => core::import::import(&current, incoming, mode),
+        Import::Interactive => {
+            let mut error = None;
+            let import =
+                core::import::import_with(&current, incoming, |current, _| {
+                    match confirm(current.name()) {
+                        Ok(true) => core::import::Resolution::Incoming,
+                        Ok(false) => core::import::Resolution::Current,
+                        Err(e) => {
+                            error.get_or_insert(e);
+                            core::import::Resolution::Current
+                        }
+                    }
+                });
+            if let Some(error) = error {
+                return Err(error);
+            }
+            import
+        }
+    };
+
+    import.executors().check_default()?;
+
+    println!("{}", import.summary());
+    if dry_run {
+        println!("Dry run: nothing was saved");
+        Ok(())
+    } else {
+        import.into_executors().save_default().map_err(Error::from)
+    }
+}
+
+/// Loads the saved configuration, without drop-in files, which is empty if nothing was saved yet
+fn load_current() -> Result<core::executors::Executors> {
+    match core::executors::load_saved() {
+        Err(core::error::Error::Read(..)) => Ok(core::executors::Executors::default()),
+        current => current.map_err(Error::from),
+    }
+}
+
+/// Loads the targets in use, saved and from drop-in files, which an incoming configuration may
+/// extend
+fn load_bases() -> Result<core::executors::Executors> {
+    match core::executors::load_default() {
+        Err(core::error::Error::Read(..)) => Ok(core::executors::Executors::default()),
+        bases => bases.map_err(Error::from),
+    }
+}
+
+fn diff(options: &[String]) -> Result {
+    let mut json = false;
+    let mut file = None;
+    for option in options {
+        match option.as_str() {
+            "--json" => json = true,
+            "-" if file.is_none() => {}
+            path if file.is_none() && !path.starts_with('-') => file = Some(path),
+            unknown => return Err(Error::UnknownCommand(String::from(unknown))),
+        }
+    }
+
+    let proposed = if let Some(file) = file {
+        let config =
+            std::fs::read_to_string(file).map_err(|e| core::error::Error::Read(file.into(), e))?;
+        core::executors::from_text_extending(&config, &load_bases()?)?
+    } else {
+        core::executors::load_from_stdin_extending(&load_bases()?)?
+    };
+
+    let diff = core::diff::diff(&load_current()?, &proposed)?;
+    if json {
+        println!("{}", diff.to_json()?);
+    } else {
+        println!("{diff}");
+    }
+    Ok(())
+}
+
+/// Asks whether the saved target `name` should be replaced by the imported one
+///
+/// The answer is read from the terminal, since stdin holds the imported configuration
+fn confirm(name: &str) -> Result<bool> {
+    use rucline::prompt::Builder;
+
+    Ok(matches!(
+        rucline::prompt::Prompt::from(format!("Replace target `{name}` with the imported one? [y/N] "))
+            .read_line()?,
+        rucline::Outcome::Accepted(answer) if answer.trim().eq_ignore_ascii_case("y")
+    ))
+}
+
+fn config(args: &[String]) -> Result {
+    match args.first().map(String::as_str) {
+        Some("backups") | None => {
+            for backup in core::backup::list()? {
+                match backup.load() {
+                    Ok(executors) => println!(
+                        "{}  {} targets",
+                        backup.id(),
+                        executors.list_targets().len()
+                    ),
+                    Err(_) => println!("{}  unreadable", backup.id()),
+                }
+            }
+            Ok(())
+        }
+        Some("restore") => {
+            let id = args.get(1).ok_or(Error::NoBackup)?;
+            let executors = core::backup::restore(id)?;
+            println!(
+                "Restored backup {id}, with {} targets",
+                executors.list_targets().len()
+            );
+            Ok(())
+        }
+        Some("targets") => {
+            for executor in &core::executors::load_default()? {
+                match executor.origin() {
+                    Some(origin) => println!("{}  {}", executor.name(), origin.display()),
+                    None => println!("{}  saved", executor.name()),
+                }
+            }
+            Ok(())
+        }
+        Some(command) => Err(Error::UnknownCommand(String::from(command))),
+    }
+}
+
 fn print_targets() -> Result {
     core::executors::load_default()?
         .list_targets()
         .into_iter()
-        .for_each(|target| println!("{}", target));
+        .for_each(|target| println!("{target}"));
     Ok(())
 }
 
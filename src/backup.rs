@@ -0,0 +1,301 @@
+//! Rotating backups of the configuration
+//!
+//! Whenever a configuration is [saved](../executors/struct.Executors.html#method.save) over a
+//! different one, the previous file is first copied into a `backups` directory next to it. Only the
+//! most recent backups are kept, 10 by default, or as many as `VAI_BACKUPS` says. Setting it to
+//! zero disables backups
+
+use super::error;
+use super::executors::{self, Executors};
+use super::Result;
+
+const BACKUP_DIR: &str = "backups";
+const DEFAULT_KEEP: usize = 10;
+
+/// A previous version of a configuration file
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Backup {
+    id: String,
+    path: std::path::PathBuf,
+}
+
+impl Backup {
+    /// Returns the identifier of this backup, which is the UTC time it was taken, as
+    /// `YYYYMMDD-hhmmss-mmm`
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns where this backup is kept
+    #[must_use]
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Loads the configuration kept in this backup
+    ///
+    /// # Errors
+    ///
+    /// * If the backup cannot be read, then [`Error(Read)`](../error/struct.Error.html)
+    /// * If the backup cannot be deserialized, then [`Error(Deserialize)`](../error/struct.Error.html)
+    pub fn load(&self) -> Result<Executors> {
+        executors::load(&self.path)
+    }
+}
+
+fn keep() -> usize {
+    std::env::var("VAI_BACKUPS")
+        .ok()
+        .and_then(|keep| keep.parse().ok())
+        .unwrap_or(DEFAULT_KEEP)
+}
+
+fn directory(config: &std::path::Path) -> std::path::PathBuf {
+    config
+        .parent()
+        .map_or_else(|| BACKUP_DIR.into(), |parent| parent.join(BACKUP_DIR))
+}
+
+fn prefix(config: &std::path::Path) -> String {
+    format!(
+        "{}.",
+        config
+            .file_name()
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or("config")
+    )
+}
+
+fn id(time: std::time::SystemTime) -> String {
+    let duration = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = duration.as_secs() % 86_400;
+    let (year, month, day) = civil(duration.as_secs() / 86_400);
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        duration.subsec_millis()
+    )
+}
+
+/// Converts days since the epoch into a (year, month, day) date, after Howard Hinnant's
+/// `civil_from_days`
+fn civil(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    (year_of_era + era * 400 + u64::from(month <= 2), month, day)
+}
+
+/// Backs up `config` before `bytes` are written over it, unless it holds exactly `bytes` already
+pub(crate) fn create(config: &std::path::Path, bytes: &[u8]) -> Result {
+    create_keeping(config, bytes, keep())
+}
+
+fn create_keeping(config: &std::path::Path, bytes: &[u8], keep: usize) -> Result {
+    if keep == 0 {
+        return Ok(());
+    }
+    let Ok(current) = std::fs::read(config) else {
+        return Ok(());
+    };
+    if current == bytes {
+        return Ok(());
+    }
+
+    let directory = directory(config);
+    std::fs::create_dir_all(&directory).map_err(|e| error::Error::Write(directory.clone(), e))?;
+
+    let prefix = prefix(config);
+    let mut path = directory.join(format!("{prefix}{}", id(std::time::SystemTime::now())));
+    // Identifiers have millisecond resolution, so two saves in a row could clash
+    while path.exists() {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        path = directory.join(format!("{prefix}{}", id(std::time::SystemTime::now())));
+    }
+    std::fs::write(&path, current).map_err(|e| error::Error::Write(path, e))?;
+
+    for backup in list_in(config)?.into_iter().skip(keep) {
+        std::fs::remove_file(&backup.path).map_err(|e| error::Error::Write(backup.path, e))?;
+    }
+    Ok(())
+}
+
+/// Lists the backups of the default configuration, newest first
+///
+/// # Errors
+///
+/// * If the path for the configuration cannot be created, then [`Error(Path)`](../error/struct.Error.html)
+/// * If the backup directory exists but cannot be read, then [`Error(Read)`](../error/struct.Error.html)
+///
+/// # See also
+/// [`list_in(config)`](fn.list_in.html)
+pub fn list() -> Result<Vec<Backup>> {
+    executors::config_path().and_then(list_in)
+}
+
+/// Lists the backups of the configuration saved at `config`, newest first
+///
+/// # Arguments
+///
+/// * `config` - Path of the configuration, as given to [`Executors::save(path)`](../executors/struct.Executors.html#method.save)
+///
+/// # Errors
+///
+/// If the backup directory exists but cannot be read, then [`Error(Read)`](../error/struct.Error.html)
+pub fn list_in<P: AsRef<std::path::Path>>(config: P) -> Result<Vec<Backup>> {
+    let directory = directory(config.as_ref());
+    let entries = match std::fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(error::Error::Read(directory, e)),
+    };
+
+    let prefix = prefix(config.as_ref());
+    let mut backups = entries
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| {
+            let id = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix(&prefix)?
+                .to_owned();
+            Some(Backup {
+                id,
+                path: entry.path(),
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+fn find(config: &std::path::Path, id: &str) -> Result<Backup> {
+    list_in(config)?
+        .into_iter()
+        .find(|backup| backup.id == id)
+        .ok_or_else(|| error::Error::UnknownBackup(String::from(id)))
+}
+
+/// Restores the backup `id` of the default configuration, returning the restored configuration
+///
+/// The configuration being replaced is backed up as well, so a restore can be undone. A backup
+/// whose targets clash with the drop-in files is not restored
+///
+/// # Arguments
+///
+/// * `id` - The [identifier](struct.Backup.html#method.id) of the backup
+///
+/// # Errors
+///
+/// * If there is no such backup, then [`Error(UnknownBackup)`](../error/struct.Error.html)
+/// * If the backup cannot be loaded or saved, the same errors as [`Backup::load()`](struct.Backup.html#method.load)
+///   and [`Executors::save_default()`](../executors/struct.Executors.html#method.save_default)
+///
+/// # See also
+/// [`restore_in(config, id)`](fn.restore_in.html)
+pub fn restore(id: &str) -> Result<Executors> {
+    let executors = find(&executors::config_path()?, id)?.load()?;
+    executors.save_default()?;
+    Ok(executors)
+}
+
+/// Restores the backup `id` of the configuration saved at `config`, returning the restored
+/// configuration
+///
+/// The configuration being replaced is backed up as well, so a restore can be undone
+///
+/// # Arguments
+///
+/// * `config` - Path of the configuration, as given to [`Executors::save(path)`](../executors/struct.Executors.html#method.save)
+/// * `id` - The [identifier](struct.Backup.html#method.id) of the backup
+///
+/// # Errors
+///
+/// * If there is no such backup, then [`Error(UnknownBackup)`](../error/struct.Error.html)
+/// * If the backup cannot be loaded or saved, the same errors as [`Backup::load()`](struct.Backup.html#method.load)
+///   and [`Executors::save(path)`](../executors/struct.Executors.html#method.save)
+pub fn restore_in<P: AsRef<std::path::Path>>(config: P, id: &str) -> Result<Executors> {
+    let executors = find(config.as_ref(), id)?.load()?;
+    executors.save(config)?;
+    Ok(executors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{directory, executors};
+
+    fn config(name: &str) -> std::path::PathBuf {
+        directory(&format!("backup_{name}")).join("config")
+    }
+
+    #[test]
+    fn test_id() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_760_000_000_500);
+        assert_eq!(id(time), "20251009-085320-500");
+        assert_eq!(id(std::time::UNIX_EPOCH), "19700101-000000-000");
+        assert_eq!(civil(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_save_backs_up_previous_configuration() {
+        let config = config("save");
+        executors(&[("a", "a")]).save(&config).unwrap();
+        assert!(list_in(&config).unwrap().is_empty());
+
+        executors(&[("a", "a"), ("b", "b")]).save(&config).unwrap();
+        executors(&[("a", "a"), ("b", "b")]).save(&config).unwrap();
+        executors(&[]).save(&config).unwrap();
+
+        let backups = list_in(&config).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].load().unwrap().list_targets(), ["a", "b"]);
+        assert_eq!(backups[1].load().unwrap().list_targets(), ["a"]);
+
+        let restored = restore_in(&config, backups[0].id()).unwrap();
+        assert_eq!(restored.list_targets(), ["a", "b"]);
+        assert_eq!(executors::load(&config).unwrap(), restored);
+        assert_eq!(list_in(&config).unwrap().len(), 3);
+
+        assert!(matches!(
+            restore_in(&config, "19700101-000000-000"),
+            Err(error::Error::UnknownBackup(_))
+        ));
+        std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_rotation() {
+        let config = config("rotation");
+        for version in 0_u8..5 {
+            std::fs::write(&config, [version]).unwrap();
+            create_keeping(&config, &[], 3).unwrap();
+        }
+
+        let backups = list_in(&config)
+            .unwrap()
+            .iter()
+            .map(|backup| std::fs::read(backup.path()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(backups, [[4], [3], [2]]);
+
+        create_keeping(&config, &[5], 0).unwrap();
+        assert_eq!(list_in(&config).unwrap().len(), 3);
+        std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+    }
+}
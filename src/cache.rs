@@ -0,0 +1,368 @@
+use serde::{Deserialize, Serialize};
+
+use super::error;
+use super::executors;
+use super::suggestion::Suggestion;
+use super::Result;
+
+const CACHE_PREFIX: &str = "suggestions_";
+const INDEX_PREFIX: &str = "index_";
+const UNREACHABLE_FILE: &str = "unreachable_hosts";
+/// How long, in seconds, an unreachable host is remembered for at most
+const UNREACHABLE_TTL: u64 = 60 * 60;
+const DEFAULT_TTL: u64 = 60 * 60;
+const DEFAULT_CAPACITY: usize = 512;
+
+static TEMPORARIES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Entry {
+    query: String,
+    timestamp: u64,
+    suggestions: Vec<Suggestion>,
+}
+
+/// The cached entries of a target, along with the fingerprint of the settings they were fetched
+/// with
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    fingerprint: u64,
+    entries: Vec<Entry>,
+}
+
+struct Settings {
+    ttl: u64,
+    capacity: usize,
+}
+
+impl Settings {
+    fn from_env() -> Self {
+        Self {
+            ttl: std::env::var("VAI_CACHE_TTL")
+                .ok()
+                .and_then(|ttl| ttl.parse().ok())
+                .unwrap_or(DEFAULT_TTL),
+            capacity: std::env::var("VAI_CACHE_SIZE")
+                .ok()
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(DEFAULT_CAPACITY),
+        }
+    }
+
+    #[inline]
+    fn enabled(&self) -> bool {
+        self.ttl > 0 && self.capacity > 0
+    }
+}
+
+fn cache_path() -> Result<std::path::PathBuf> {
+    std::env::var("VAI_CACHE")
+        .map(std::path::PathBuf::from)
+        .or_else(|_| match dirs::cache_dir() {
+            Some(path) => Ok(path.join("vai")),
+            None => executors::default_path().map(|path| path.join("cache")),
+        })
+}
+
+/// Returns `prefix` followed by `target`, escaping every character but ASCII letters, digits, `-`
+/// and `_`, so that any target name makes a single file name
+fn file_name(prefix: &str, target: &str) -> String {
+    use std::fmt::Write;
+
+    let mut name = String::from(prefix);
+    for byte in target.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            name.push(char::from(byte));
+        } else {
+            // Writing to a String never fails
+            write!(name, "%{byte:02X}").ok();
+        }
+    }
+    name
+}
+
+fn target_path(target: &str) -> Result<std::path::PathBuf> {
+    cache_path().map(|path| path.join(file_name(CACHE_PREFIX, target)))
+}
+
+/// Returns where the index of the [word list](../wordlist/struct.WordList.html) of `target` is kept
+pub(crate) fn index_path(target: &str) -> Result<std::path::PathBuf> {
+    cache_path().map(|path| path.join(file_name(INDEX_PREFIX, target)))
+}
+
+/// Hashes `settings` into the fingerprint cached suggestions are stored with, so that they are
+/// dropped as soon as the settings they were fetched with change
+pub(crate) fn fingerprint<T: Serialize>(settings: &T) -> u64 {
+    // FNV-1a, which unlike the standard hasher gives the same result across Rust versions
+    bincode::serialize(settings)
+        .unwrap_or_default()
+        .into_iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Writes `bytes` to `path` through a temporary file renamed into place, so that other processes
+/// never read a partially written file
+pub(crate) fn store(path: &std::path::Path, bytes: &[u8]) -> Result {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| error::Error::Write(parent.into(), e))?;
+    }
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(
+        ".{}_{}.tmp",
+        std::process::id(),
+        TEMPORARIES.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    let temporary = std::path::PathBuf::from(temporary);
+    std::fs::write(&temporary, bytes).map_err(|e| error::Error::Write(temporary.clone(), e))?;
+    std::fs::rename(&temporary, path).map_err(|e| {
+        std::fs::remove_file(&temporary).ok();
+        error::Error::Write(path.into(), e)
+    })
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Reads the entries at `path`, which are only kept if they were stored with `fingerprint`
+fn read(path: &std::path::Path, fingerprint: u64) -> Vec<Entry> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| bincode::deserialize::<Cached>(&bytes).ok())
+        .filter(|cached| cached.fingerprint == fingerprint)
+        .map(|cached| cached.entries)
+        .unwrap_or_default()
+}
+
+fn write(path: &std::path::Path, fingerprint: u64, entries: Vec<Entry>) -> Result {
+    let bytes = bincode::serialize(&Cached {
+        fingerprint,
+        entries,
+    })
+    .map_err(error::Serialize::from)
+    .map_err(error::Error::Serialize)?;
+    store(path, &bytes)
+}
+
+fn lookup(entries: &[Entry], query: &str, now: u64, ttl: u64) -> Option<Vec<Suggestion>> {
+    entries
+        .iter()
+        .find(|entry| entry.query == query)
+        .filter(|entry| now.saturating_sub(entry.timestamp) < ttl)
+        .map(|entry| entry.suggestions.clone())
+}
+
+fn insert(entries: &mut Vec<Entry>, entry: Entry, capacity: usize) {
+    entries.retain(|existing| existing.query != entry.query);
+    entries.push(entry);
+    if entries.len() > capacity {
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        entries.truncate(capacity);
+    }
+}
+
+/// Returns the cached suggestions for `query` on `target`, if present and not yet expired
+///
+/// Only suggestions stored with the same `fingerprint` are returned
+pub(crate) fn get(target: &str, fingerprint: u64, query: &str) -> Option<Vec<Suggestion>> {
+    let settings = Settings::from_env();
+    if !settings.enabled() {
+        return None;
+    }
+
+    let path = target_path(target).ok()?;
+    lookup(&read(&path, fingerprint), query, now(), settings.ttl)
+}
+
+/// Returns the cached suggestions for `query` on `target`, even if already expired
+///
+/// Useful as a fallback when the suggestion API cannot be reached
+pub(crate) fn get_stale(target: &str, fingerprint: u64, query: &str) -> Option<Vec<Suggestion>> {
+    let path = target_path(target).ok()?;
+    lookup(&read(&path, fingerprint), query, now(), u64::MAX)
+}
+
+/// Stores `suggestions` for `query` on `target`, evicting the oldest entries past the size cap
+///
+/// Entries stored with another `fingerprint` are dropped
+pub(crate) fn put(
+    target: &str,
+    fingerprint: u64,
+    query: &str,
+    suggestions: &[Suggestion],
+) -> Result {
+    let settings = Settings::from_env();
+    if !settings.enabled() {
+        return Ok(());
+    }
+
+    // Expired entries are kept until evicted, as they are still useful while offline
+    let path = target_path(target)?;
+    let mut entries = read(&path, fingerprint);
+    insert(
+        &mut entries,
+        Entry {
+            query: String::from(query),
+            timestamp: now(),
+            suggestions: suggestions.to_vec(),
+        },
+        settings.capacity,
+    );
+    write(&path, fingerprint, entries)
+}
+
+/// Returns when a request last failed to reach `host`, in seconds since the Unix epoch, as recorded
+/// by any process
+pub(crate) fn unreachable_since(host: &str) -> Option<u64> {
+    let path = cache_path().ok()?.join(UNREACHABLE_FILE);
+    read_unreachable(&path).get(host).copied()
+}
+
+/// Records whether the last request to `host` failed to reach it, so that other processes do not
+/// wait for it again
+pub(crate) fn set_unreachable(host: &str, unreachable: bool) -> Result {
+    set_unreachable_in(
+        &cache_path()?.join(UNREACHABLE_FILE),
+        host,
+        unreachable,
+        now(),
+    )
+}
+
+fn set_unreachable_in(path: &std::path::Path, host: &str, unreachable: bool, now: u64) -> Result {
+    let mut hosts = read_unreachable(path);
+    if unreachable {
+        hosts.retain(|_, since| now.saturating_sub(*since) < UNREACHABLE_TTL);
+        hosts.insert(String::from(host), now);
+    } else if hosts.remove(host).is_none() {
+        return Ok(());
+    }
+
+    let bytes = bincode::serialize(&hosts)
+        .map_err(error::Serialize::from)
+        .map_err(error::Error::Serialize)?;
+    store(path, &bytes)
+}
+
+fn read_unreachable(path: &std::path::Path) -> std::collections::HashMap<String, u64> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Removes all cached suggestions, word list indexes and unreachable hosts
+///
+/// # Errors
+///
+/// * If the path for the cache cannot be created, then [`Error(Path)`](../error/struct.Error.html)
+/// * If a cache file cannot be removed, then [`Error(Write)`](../error/struct.Error.html)
+pub fn clear() -> Result {
+    let path = cache_path()?;
+    let Ok(entries) = std::fs::read_dir(&path) else {
+        return Ok(());
+    };
+
+    for entry in entries.filter_map(std::result::Result::ok) {
+        if entry.file_name().to_str().is_some_and(|name| {
+            name.starts_with(CACHE_PREFIX)
+                || name.starts_with(INDEX_PREFIX)
+                || name.starts_with(UNREACHABLE_FILE)
+        }) {
+            let path = entry.path();
+            std::fs::remove_file(&path).map_err(|e| error::Error::Write(path, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suggestion::Origin;
+
+    fn entry(query: &str, timestamp: u64) -> Entry {
+        Entry {
+            query: String::from(query),
+            timestamp,
+            suggestions: vec![Suggestion::new(query, Origin::Remote)],
+        }
+    }
+
+    #[test]
+    fn test_lookup_respects_ttl() {
+        let entries = vec![entry("rust", 100)];
+        assert!(lookup(&entries, "rust", 150, 60).is_some());
+        assert!(lookup(&entries, "rust", 160, 60).is_none());
+        assert!(lookup(&entries, "rus", 150, 60).is_none());
+    }
+
+    #[test]
+    fn test_insert_replaces_and_evicts() {
+        let mut entries = vec![entry("a", 1), entry("b", 2)];
+        insert(&mut entries, entry("a", 3), 2);
+        assert_eq!(entries, vec![entry("b", 2), entry("a", 3)]);
+
+        insert(&mut entries, entry("c", 4), 2);
+        assert_eq!(entries, vec![entry("c", 4), entry("a", 3)]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let directory = crate::testing::directory("cache");
+        let path = directory.join("rust");
+        write(&path, 1, vec![entry("rust", 10), entry("serde", 20)]).unwrap();
+        assert_eq!(read(&path, 1), vec![entry("rust", 10), entry("serde", 20)]);
+        assert!(read(&path, 2).is_empty());
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+        std::fs::remove_file(&path).unwrap();
+        assert!(read(&path, 1).is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_unreachable_hosts() {
+        let directory = crate::testing::directory("unreachable");
+        let path = directory.join(UNREACHABLE_FILE);
+        set_unreachable_in(&path, "example.com", false, 100).unwrap();
+        assert!(!path.exists());
+
+        set_unreachable_in(&path, "example.com", true, 100).unwrap();
+        set_unreachable_in(&path, "example.org", true, 200).unwrap();
+        assert_eq!(read_unreachable(&path).get("example.com"), Some(&100));
+
+        set_unreachable_in(&path, "example.com", false, 300).unwrap();
+        assert_eq!(read_unreachable(&path).len(), 1);
+
+        set_unreachable_in(&path, "example.net", true, 200 + UNREACHABLE_TTL).unwrap();
+        assert_eq!(
+            read_unreachable(&path).into_keys().collect::<Vec<_>>(),
+            ["example.net"]
+        );
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(
+            file_name(CACHE_PREFIX, "rust-docs_2"),
+            "suggestions_rust-docs_2"
+        );
+        assert_eq!(
+            file_name(INDEX_PREFIX, "../../.bashrc"),
+            "index_%2E%2E%2F%2E%2E%2F%2Ebashrc"
+        );
+        assert_eq!(file_name(CACHE_PREFIX, "wü"), "suggestions_w%C3%BC");
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(fingerprint(&("a", 1)), fingerprint(&("a", 1)));
+        assert_ne!(fingerprint(&("a", 1)), fingerprint(&("a", 2)));
+    }
+}
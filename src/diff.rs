@@ -0,0 +1,359 @@
+//! Differences between two configurations, target by target and field by field
+
+use serde::Serialize;
+
+use super::error;
+use super::executors::{Executor, Executors};
+use super::Result;
+
+/// How a target differs between two configurations
+#[derive(Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Change {
+    /// The target only exists in the proposed configuration
+    Added,
+    /// The target only exists in the current configuration
+    Removed,
+    /// The target exists in both, but some of its fields differ
+    Changed,
+}
+
+/// A field that differs between the current and the proposed version of a target
+///
+/// Fields nested in objects, such as `http`, are compared one by one, while any other value is
+/// compared as a whole
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct Field {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<serde_json::Value>,
+}
+
+impl Field {
+    /// Returns the path of the field, with nested fields separated by dots, such as `http.proxy`
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the current value of the field, if it is set
+    #[must_use]
+    pub fn before(&self) -> Option<&serde_json::Value> {
+        self.before.as_ref()
+    }
+
+    /// Returns the proposed value of the field, if it is set
+    #[must_use]
+    pub fn after(&self) -> Option<&serde_json::Value> {
+        self.after.as_ref()
+    }
+}
+
+/// A target that differs between two configurations
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct Target {
+    name: String,
+    change: Change,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<Field>,
+}
+
+impl Target {
+    /// Returns the name of the target
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns how the target differs
+    #[must_use]
+    pub fn change(&self) -> Change {
+        self.change
+    }
+
+    /// Returns the fields that differ, in alphabetical order, if the target was
+    /// [changed](enum.Change.html#variant.Changed)
+    #[must_use]
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+}
+
+/// Every target that differs between two configurations
+///
+/// Targets are matched by name. Removed and changed targets are listed in their current order,
+/// followed by the added targets in their proposed order
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(transparent)]
+pub struct Diff {
+    targets: Vec<Target>,
+}
+
+impl Diff {
+    /// Returns the targets that differ
+    #[must_use]
+    pub fn targets(&self) -> &[Target] {
+        &self.targets
+    }
+
+    /// Returns `true` if both configurations are the same
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Output this `Diff` as a json representation
+    ///
+    /// # Errors
+    ///
+    /// * If the diff cannot be serialized, then [`Error(Serialize)`](../error/struct.Error.html)
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self)
+            .map_err(error::Serialize::from)
+            .map_err(error::Error::Serialize)
+    }
+}
+
+impl std::fmt::Display for Diff {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(fmt, "No differences");
+        }
+
+        let mut separator = "";
+        for target in &self.targets {
+            let sign = match target.change {
+                Change::Added => '+',
+                Change::Removed => '-',
+                Change::Changed => '~',
+            };
+            write!(fmt, "{separator}{sign} {}", target.name)?;
+            separator = "\n";
+
+            for field in &target.fields {
+                match (&field.before, &field.after) {
+                    (Some(before), Some(after)) => {
+                        write!(fmt, "\n    ~ {}: {before} -> {after}", field.path)?;
+                    }
+                    (Some(before), None) => write!(fmt, "\n    - {}: {before}", field.path)?,
+                    (None, Some(after)) => write!(fmt, "\n    + {}: {after}", field.path)?,
+                    (None, None) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_value(executor: &Executor) -> Result<serde_json::Value> {
+    serde_json::to_value(executor)
+        .map_err(error::Serialize::from)
+        .map_err(error::Error::Serialize)
+}
+
+/// Gathers the differences between `before` and `after` into `fields`, descending into objects
+fn compare(
+    path: &str,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+    fields: &mut Vec<Field>,
+) {
+    use serde_json::Value;
+
+    if before == after {
+        return;
+    }
+
+    if let (Some(Value::Object(before)), Some(Value::Object(after))) = (before, after) {
+        let mut keys = before.keys().collect::<Vec<_>>();
+        keys.extend(after.keys().filter(|key| !before.contains_key(*key)));
+        for key in keys {
+            let path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            compare(&path, before.get(key), after.get(key), fields);
+        }
+    } else {
+        fields.push(Field {
+            path: String::from(path),
+            before: before.cloned(),
+            after: after.cloned(),
+        });
+    }
+}
+
+/// Compares the `current` configuration with a `proposed` one, target by target and field by field
+///
+/// # Arguments
+///
+/// * `current` - The configuration in use
+/// * `proposed` - The configuration it would be replaced with
+///
+/// # Errors
+///
+/// * If a target cannot be serialized, then [`Error(Serialize)`](../error/struct.Error.html)
+pub fn diff(current: &Executors, proposed: &Executors) -> Result<Diff> {
+    let mut targets = Vec::new();
+
+    for executor in current {
+        match proposed
+            .iter()
+            .find(|other| other.name() == executor.name())
+        {
+            Some(other) if other == executor => {}
+            Some(other) => {
+                let mut fields = Vec::new();
+                compare(
+                    "",
+                    Some(&to_value(executor)?),
+                    Some(&to_value(other)?),
+                    &mut fields,
+                );
+                targets.push(Target {
+                    name: String::from(executor.name()),
+                    change: Change::Changed,
+                    fields,
+                });
+            }
+            None => targets.push(Target {
+                name: String::from(executor.name()),
+                change: Change::Removed,
+                fields: Vec::new(),
+            }),
+        }
+    }
+
+    for executor in proposed {
+        if !current.iter().any(|other| other.name() == executor.name()) {
+            targets.push(Target {
+                name: String::from(executor.name()),
+                change: Change::Added,
+                fields: Vec::new(),
+            });
+        }
+    }
+
+    Ok(Diff { targets })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current() -> Executors {
+        crate::executors::from_json(
+            r#"[
+                {"name": "google", "alias": "g", "command": "https://google.com/?q=",
+                 "suggestion": "https://google.com/s?q=", "parser": "GOOGLE"},
+                {"name": "duck", "alias": "d", "command": "https://duck.com/?q=",
+                 "suggestion": "", "parser": "NONE"},
+                {"name": "wiki", "alias": "w", "command": "https://w.org/?q=",
+                 "suggestion": "https://w.org/s?q=", "parser": {"JSON": {"path": "/1"}},
+                 "http": {"proxy": "http://proxy:8080"}}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn proposed() -> Executors {
+        crate::executors::from_json(
+            r#"[
+                {"name": "wiki", "alias": "w", "command": "https://wikipedia.org/?q=",
+                 "suggestion": "https://w.org/s?q=", "parser": "OPENSEARCH",
+                 "http": {"proxy": "socks5://proxy:1080"}},
+                {"name": "google", "alias": "g", "command": "https://google.com/?q=",
+                 "suggestion": "https://google.com/s?q=", "parser": "GOOGLE"},
+                {"name": "rust", "alias": "r", "command": "https://docs.rs/?q=",
+                 "suggestion": "", "parser": "NONE"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_targets() {
+        let diff = diff(&current(), &proposed()).unwrap();
+        let targets = diff
+            .targets()
+            .iter()
+            .map(|target| (target.name(), target.change()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            [
+                ("duck", Change::Removed),
+                ("wiki", Change::Changed),
+                ("rust", Change::Added)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fields() {
+        let diff = diff(&current(), &proposed()).unwrap();
+        let fields = diff.targets()[1]
+            .fields()
+            .iter()
+            .map(Field::path)
+            .collect::<Vec<_>>();
+        assert_eq!(fields, ["command", "http.proxy", "parser"]);
+
+        let parser = &diff.targets()[1].fields()[2];
+        assert!(parser.before().unwrap()["JSON"].is_object());
+        assert_eq!(parser.after(), Some(&serde_json::json!("OPEN_SEARCH")));
+    }
+
+    #[test]
+    fn test_display() {
+        let diff = diff(&current(), &proposed()).unwrap();
+        let display = diff.to_string();
+        let lines = display.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "- duck");
+        assert_eq!(lines[1], "~ wiki");
+        assert_eq!(
+            lines[2],
+            r#"    ~ command: "https://w.org/?q=" -> "https://wikipedia.org/?q=""#
+        );
+        assert_eq!(
+            lines[3],
+            r#"    ~ http.proxy: "http://proxy:8080" -> "socks5://proxy:1080""#
+        );
+        assert!(lines[4].starts_with(r#"    ~ parser: {"JSON":"#));
+        assert!(lines[4].ends_with(r#"} -> "OPEN_SEARCH""#));
+        assert_eq!(lines[5], "+ rust");
+    }
+
+    #[test]
+    fn test_json() {
+        let diff = diff(&current(), &proposed()).unwrap();
+        let json = serde_json::from_str::<serde_json::Value>(&diff.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({"name": "duck", "change": "REMOVED"})
+        );
+        assert_eq!(
+            json[1]["fields"][0],
+            serde_json::json!({
+                "path": "command",
+                "before": "https://w.org/?q=",
+                "after": "https://wikipedia.org/?q="
+            })
+        );
+        assert_eq!(
+            json[2],
+            serde_json::json!({"name": "rust", "change": "ADDED"})
+        );
+    }
+
+    #[test]
+    fn test_no_differences() {
+        let diff = diff(&current(), &current()).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "No differences");
+    }
+}
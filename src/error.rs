@@ -3,8 +3,10 @@
 pub enum Error {
     #[error("Could not infer configuration directory")]
     Path,
+    #[error("Cannot fetch suggestions while offline")]
+    Offline,
     #[error("Failed to fetch suggestions: {0}")]
-    Fetch(#[from] ureq::Error),
+    Fetch(Box<ureq::Error>),
     #[error("Could not write to {0}: {1}")]
     Write(std::path::PathBuf, std::io::Error),
     #[error("Could not read from {0}: {1}")]
@@ -17,6 +19,20 @@ pub enum Error {
     Parse(Parse),
     #[error("Could not open query in a browser: {0}")]
     Browser(std::io::Error),
+    #[error("Suggestion command `{0}` failed: {1}")]
+    Process(String, Process),
+    #[error("Invalid configuration:{}", crate::validation::list(.0))]
+    Invalid(Vec<crate::validation::Problem>),
+    #[error("In {0}: {1}")]
+    Include(std::path::PathBuf, Box<Error>),
+    #[error("No backup with id `{0}`")]
+    UnknownBackup(String),
+}
+
+impl std::convert::From<ureq::Error> for Error {
+    fn from(error: ureq::Error) -> Self {
+        Self::Fetch(Box::new(error))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -25,6 +41,10 @@ pub enum Serialize {
     Binary(#[from] bincode::Error),
     #[error(transparent)]
     Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::ser::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -35,10 +55,36 @@ pub enum Deserialize {
     Json(#[from] serde_json::Error),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum Process {
+    #[error("could not be started: {0}")]
+    Spawn(std::io::Error),
+    #[error("did not finish within {0} ms")]
+    Timeout(u64),
+    #[error("exited with {0}: {1}")]
+    Status(std::process::ExitStatus, String),
+    #[error(transparent)]
+    Io(std::io::Error),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Parse {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error("No array of suggestions at `{0}`")]
+    Path(String),
+    #[error(transparent)]
+    Xml(#[from] roxmltree::Error),
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
+    #[error("Unknown charset `{0}`")]
+    Charset(String),
+    #[error("Response is not wrapped in the expected JSONP callback")]
+    Jsonp(Option<String>),
+    #[error("No parser registered as `{0}`")]
+    Unknown(String),
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
     #[error(transparent)]
     Json(#[from] serde_json::Error),
 }
@@ -1,11 +1,24 @@
 use serde::{Deserialize, Serialize};
 
+use super::backup;
+use super::cache;
 use super::error;
+use super::fetcher;
+use super::format::{self, Format};
+use super::http;
+use super::inheritance;
+use super::lenient;
 use super::parser;
+use super::process;
+use super::suggestion::{self, Origin, Suggestion};
+use super::validation;
+use super::wordlist;
 use super::Result;
 
 const HISTORY_PREFIX: &str = "history_";
 const CONFIG_FILE: &str = "config";
+const INCLUDE_DIR: &str = "targets.d";
+const DEFAULT_MIN_LENGTH: usize = 3;
 
 #[derive(Debug, PartialEq, Eq)]
 struct FuzzyMatch(i64, String);
@@ -20,8 +33,8 @@ impl FuzzyMatch {
     }
 
     #[inline]
-    fn matched(self) -> String {
-        self.1
+    fn into_suggestion(self) -> Suggestion {
+        Suggestion::new(self.1, Origin::History).with_score(self.0)
     }
 }
 
@@ -33,33 +46,307 @@ impl Ord for FuzzyMatch {
 
 impl PartialOrd for FuzzyMatch {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
+/// Where a [target](struct.Executor.html) gets its remote suggestions from
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, PartialEq, Debug, Default)]
+#[serde(remote = "Self", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Source {
+    /// The suggestion API at the `suggestion` URL
+    #[default]
+    Http,
+    /// A local program, given the query
+    Process(process::Process),
+    /// A local file with a fixed vocabulary, fuzzy matched against the query
+    WordList(wordlist::WordList),
+}
+
+lenient::lenient_enum!(Source);
+
 /// Represents a target for querying
 ///
 /// Must contain a URL to be called by the browser
 ///
 /// May contain a URL for querying for suggestions, along with it parser
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, Debug)]
 pub struct Executor {
+    /// Name of the target, referenced when calling `vai`
     name: String,
+    /// Alias for the target, usually shorter than `name`
     alias: String,
+    /// URL to use when calling the browser for this target. Query will be appended to it
     command: String,
+    /// URL to use for suggestions from the target. Query will be appended to it
     suggestion: String,
+    /// How to parse the suggestions
     parser: parser::Parser,
+    /// HTTP settings for the suggestion URL, overriding the global ones
+    #[serde(default)]
+    http: http::Settings,
+    /// Minimum query length, in characters, to fetch suggestions
+    #[serde(default = "default_min_length")]
+    min_length: usize,
+    /// Maximum query length, in characters, to fetch suggestions
+    #[serde(default)]
+    max_length: Option<usize>,
+    /// Whether to fetch suggestions for whitespace-only queries
+    #[serde(default)]
+    suggest_blank: bool,
+    /// Charset of the suggestion responses, overriding the one declared by the server
+    #[serde(default)]
+    charset: Option<String>,
+    /// Where suggestions come from
+    #[serde(default)]
+    source: Source,
+    /// Target to take every field but `name` and `alias` from, unless set in this one
+    #[serde(default)]
+    extends: Option<String>,
+    /// Text added as is before the query sent to the browser, such as `site:docs.rs `
+    #[serde(default)]
+    prefix: String,
+    /// Text added as is after the query sent to the browser, such as `&type=issues`
+    #[serde(default)]
+    suffix: String,
+    /// The fields of a derived target as written, saved instead of the resolved ones so that it
+    /// follows later changes to its base
+    #[serde(skip)]
+    declared: Option<serde_json::Value>,
+    #[serde(skip)]
+    parsers: parser::Registry,
+    #[serde(skip)]
+    origin: DropIn,
+}
+
+/// The drop-in file a target was loaded from, if it was not loaded from the saved configuration
+#[derive(Clone, Default, Debug)]
+struct DropIn(Option<std::path::PathBuf>);
+
+impl std::fmt::Display for DropIn {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(path) => write!(fmt, "`{}`", path.display()),
+            None => write!(fmt, "the saved configuration"),
+        }
+    }
+}
+
+/// Compares the configuration of two targets, leaving out the parsers they share and the file they
+/// were loaded from
+impl PartialEq for Executor {
+    fn eq(&self, other: &Self) -> bool {
+        let Self {
+            name,
+            alias,
+            command,
+            suggestion,
+            parser,
+            http,
+            min_length,
+            max_length,
+            suggest_blank,
+            charset,
+            source,
+            extends,
+            prefix,
+            suffix,
+            declared,
+            parsers: _,
+            origin: _,
+        } = self;
+        *name == other.name
+            && *alias == other.alias
+            && *command == other.command
+            && *suggestion == other.suggestion
+            && *parser == other.parser
+            && *http == other.http
+            && *min_length == other.min_length
+            && *max_length == other.max_length
+            && *suggest_blank == other.suggest_blank
+            && *charset == other.charset
+            && *source == other.source
+            && *extends == other.extends
+            && *prefix == other.prefix
+            && *suffix == other.suffix
+            && *declared == other.declared
+    }
+}
+
+#[inline]
+fn default_min_length() -> usize {
+    DEFAULT_MIN_LENGTH
 }
 
 impl Executor {
     fn clean_up_name(self) -> Self {
+        let mut declared = self.declared;
+        if let Some(serde_json::Value::Object(fields)) = &mut declared {
+            for field in ["name", "alias"] {
+                if let Some(serde_json::Value::String(value)) = fields.get_mut(field) {
+                    *value = value.to_lowercase();
+                }
+            }
+        }
         Self {
             name: self.name.to_lowercase(),
             alias: self.alias.to_lowercase(),
-            command: self.command,
-            suggestion: self.suggestion,
-            parser: self.parser,
+            declared,
+            ..self
+        }
+    }
+
+    /// Keeps `declared` as the fields written for this target, which extends another one
+    pub(crate) fn declaring(self, declared: serde_json::Value) -> Self {
+        Self {
+            declared: Some(declared),
+            ..self
+        }
+    }
+
+    /// Returns the fields written for this target if it extends another one
+    pub(crate) fn declared(&self) -> Option<&serde_json::Value> {
+        self.declared.as_ref()
+    }
+
+    /// Checks the query against the length rules of this target, counting grapheme clusters
+    fn accepts(&self, query: &str) -> bool {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let trimmed = query.trim();
+        if trimmed.is_empty() && !query.is_empty() && !self.suggest_blank {
+            return false;
         }
+
+        let length = trimmed.graphemes(true).count();
+        length >= self.min_length && self.max_length.is_none_or(|max| length <= max)
+    }
+
+    /// Returns the problems with this target, with fields relative to it
+    fn problems(&self) -> Vec<validation::Problem> {
+        use validation::Problem;
+
+        let mut problems = Vec::new();
+        for (field, value) in [("name", &self.name), ("alias", &self.alias)] {
+            if value.contains(char::is_whitespace) {
+                problems.push(Problem::new(
+                    field,
+                    format!("`{value}` contains whitespace, so it cannot be typed as a target"),
+                    Some(format!(
+                        "use `{}`",
+                        value.split_whitespace().collect::<Vec<_>>().join("-")
+                    )),
+                ));
+            }
+        }
+        if self.name.is_empty() {
+            problems.push(Problem::new(
+                "name",
+                "Empty name",
+                Some(String::from("give the target a name, such as `google`")),
+            ));
+        }
+
+        if self.command.is_empty() {
+            problems.push(Problem::new(
+                "command",
+                "Empty command",
+                Some(String::from(
+                    "set `command` to the URL the query is appended to, such as `https://www.google.com/search?q=`",
+                )),
+            ));
+        } else {
+            problems.extend(validation::url("command", &self.command));
+        }
+        if !self.suggestion.is_empty() {
+            problems.extend(validation::url("suggestion", &self.suggestion));
+        }
+
+        problems.extend(self.source_problems());
+        problems.extend(
+            self.parser
+                .problems()
+                .into_iter()
+                .map(|problem| problem.under("parser")),
+        );
+
+        if let Some(max_length) = self.max_length.filter(|max| *max < self.min_length) {
+            problems.push(Problem::new(
+                "max_length",
+                format!(
+                    "Shorter than `min_length`, which is {}, so no query is ever long enough",
+                    self.min_length
+                ),
+                Some(format!(
+                    "set `max_length` to {} or more, or lower `min_length` below {max_length}",
+                    self.min_length
+                )),
+            ));
+        }
+        if let Some(charset) = &self.charset {
+            if encoding_rs::Encoding::for_label(charset.trim().as_bytes()).is_none() {
+                problems.push(Problem::new(
+                    "charset",
+                    format!("Unknown charset `{charset}`"),
+                    Some(String::from(
+                        "use a charset label such as `utf-8` or `iso-8859-1`, or remove `charset`",
+                    )),
+                ));
+            }
+        }
+        problems.extend(
+            self.http
+                .problems()
+                .into_iter()
+                .map(|problem| problem.under("http")),
+        );
+        problems
+    }
+
+    /// Returns the problems with where suggestions come from, with fields relative to the target
+    fn source_problems(&self) -> Vec<validation::Problem> {
+        use validation::Problem;
+
+        let mut problems = Vec::new();
+        let parsed = self.parser != parser::Parser::None;
+        match &self.source {
+            Source::Http if !self.suggestion.is_empty() && !parsed => problems.push(Problem::new(
+                "suggestion",
+                "Never used, since `parser` is `NONE`",
+                Some(String::from(
+                    "set `parser` to how the suggestion responses are parsed, or set `suggestion` to `\"\"`",
+                )),
+            )),
+            Source::Http if self.suggestion.is_empty() && parsed => problems.push(Problem::new(
+                "parser",
+                "Never used, since `suggestion` is empty",
+                Some(String::from(
+                    "set `suggestion` to the URL of the suggestion API, or set `parser` to `NONE`",
+                )),
+            )),
+            Source::Http => {}
+            Source::Process(process) => {
+                if !parsed {
+                    problems.push(Problem::new(
+                        "parser",
+                        "The output of the program is never used, since `parser` is `NONE`",
+                        Some(String::from("set `parser` to `LINES` or to how the output is parsed")),
+                    ));
+                }
+                problems.extend(
+                    process
+                        .problems()
+                        .into_iter()
+                        .map(|problem| problem.under("source.PROCESS")),
+                );
+            }
+            Source::WordList(list) => problems.extend(
+                list.problems()
+                    .into_iter()
+                    .map(|problem| problem.under("source.WORD_LIST")),
+            ),
+        }
+        problems
     }
 
     fn save_history(&self, query: &str) -> Result {
@@ -74,7 +361,7 @@ impl Executor {
                     .collect::<Vec<&str>>()
                     .join("\n")
             });
-        let data = format!("{}\n{}\n", query, history);
+        let data = format!("{query}\n{history}\n");
         std::fs::write(&path, data).map_err(|e| error::Error::Write(path, e))
     }
 
@@ -84,6 +371,32 @@ impl Executor {
         &self.name
     }
 
+    /// Returns the alias associated with this executor
+    #[must_use]
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    /// Returns the URL the query is appended to when executing
+    #[must_use]
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Returns the drop-in file this target was loaded from, or `None` if it comes from the saved
+    /// configuration
+    #[must_use]
+    pub fn origin(&self) -> Option<&std::path::Path> {
+        self.origin.0.as_deref()
+    }
+
+    /// Returns `true` if the suggestion API of this target is not queried, either because of
+    /// [offline mode](../http/fn.is_offline.html) or because its host could not be reached recently
+    #[must_use]
+    pub fn is_offline(&self) -> bool {
+        http::is_offline_for(&self.suggestion)
+    }
+
     /// Executes the query by calling the default browser
     ///
     /// # Arguments
@@ -95,11 +408,36 @@ impl Executor {
     /// * If `webbrowser::open(&str)` fails, then [`Error(Browser)`](../error/struct.Error.html)
     /// * If the history cannot be saved, then [`Error(Write)`](../error/struct.Error.html)
     pub fn execute(&self, query: &str) -> Result {
-        let url = format!("{}{}", &self.command, query);
-        webbrowser::open(url.as_str()).map_err(error::Error::Browser)?;
+        webbrowser::open(self.url(query).as_str()).map_err(error::Error::Browser)?;
         self.save_history(query)
     }
 
+    /// Builds the URL opened for `query`, with the `prefix` and `suffix` of this target around it
+    fn url(&self, query: &str) -> String {
+        format!("{}{}{query}{}", self.command, self.prefix, self.suffix)
+    }
+
+    /// Executes the suggestion by calling the default browser
+    ///
+    /// If the suggestion carries a direct URL, it is opened instead of querying the target
+    ///
+    /// # Arguments
+    ///
+    /// * `suggestion` - Suggestion to execute on `target`
+    ///
+    /// # Errors
+    ///
+    /// * If `webbrowser::open(&str)` fails, then [`Error(Browser)`](../error/struct.Error.html)
+    /// * If the history cannot be saved, then [`Error(Write)`](../error/struct.Error.html)
+    pub fn execute_suggestion(&self, suggestion: &Suggestion) -> Result {
+        if let Some(url) = suggestion.url() {
+            webbrowser::open(url).map_err(error::Error::Browser)?;
+            self.save_history(suggestion.text())
+        } else {
+            self.execute(suggestion.text())
+        }
+    }
+
     /// Returns the complete history from file
     ///
     /// # Errors
@@ -116,7 +454,7 @@ impl Executor {
             .open(path)
             .map(std::io::BufReader::new)
             .map(std::io::BufReader::lines)
-            .map(|lines| lines.filter_map(std::result::Result::ok).collect())
+            .map(|lines| lines.map_while(std::result::Result::ok).collect())
             .or_else(|_| Ok(vec![]))
     }
 
@@ -130,7 +468,26 @@ impl Executor {
     /// # Errors
     ///
     /// * If the path for the history cannot be created, then [`Error(Path)`](../error/struct.Error.html)
+    ///
+    /// # See also
+    /// [`history_suggestions(query, count)`](#method.history_suggestions)
     pub fn fuzzy_history(&self, query: &str, count: usize) -> Result<Vec<String>> {
+        self.history_suggestions(query, count)
+            .map(|suggestions| suggestions.into_iter().map(Suggestion::into_text).collect())
+    }
+
+    /// Suggest up to `count` [`Suggestions`](../suggestion/struct.Suggestion.html) based on fuzzy
+    /// matching of the history, scored by how well they match `query`
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query string to to get historic completions for
+    /// * `count` - Maximum number of items to return
+    ///
+    /// # Errors
+    ///
+    /// * If the path for the history cannot be created, then [`Error(Path)`](../error/struct.Error.html)
+    pub fn history_suggestions(&self, query: &str, count: usize) -> Result<Vec<Suggestion>> {
         use std::io::BufRead;
 
         let path =
@@ -144,19 +501,71 @@ impl Executor {
             .map(std::io::BufReader::lines)
             .map(|lines| {
                 let mut completions = lines
-                    .filter_map(std::result::Result::ok)
+                    .map_while(std::result::Result::ok)
                     .filter_map(|line| FuzzyMatch::new(line, query, &fuzzy))
                     .collect::<Vec<_>>();
-                completions.sort_unstable();
+                completions.sort_by(|a, b| b.cmp(a));
                 completions
                     .into_iter()
                     .take(count)
-                    .map(FuzzyMatch::matched)
+                    .map(FuzzyMatch::into_suggestion)
                     .collect()
             })
             .or_else(|_| Ok(vec![]))
     }
 
+    /// Suggest up to `count` queries from both the history and the suggestion API
+    ///
+    /// Both sources are merged according to `ranking` and entries that differ only by case or
+    /// whitespace are reported once. If the suggestion API cannot be used, previously cached
+    /// suggestions are used instead and the reason is reported in the
+    /// [`Status`](../suggestion/enum.Status.html)
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query string to to get suggestions for
+    /// * `count` - Maximum number of items to return
+    /// * `ranking` - How to interleave history and remote suggestions
+    ///
+    /// # Errors
+    ///
+    /// * If the path for the history cannot be created, then [`Error(Path)`](../error/struct.Error.html)
+    pub fn ranked_suggestions(
+        &self,
+        query: &str,
+        count: usize,
+        ranking: suggestion::Ranking,
+    ) -> Result<suggestion::Suggestions> {
+        let history = self.history_suggestions(query, count)?;
+        let (remote, status) = self.remote(query);
+        Ok(suggestion::Suggestions::new(
+            suggestion::merge(history, remote, ranking, count),
+            status,
+        ))
+    }
+
+    /// Creates a [`Fetcher`](../fetcher/struct.Fetcher.html) that provides
+    /// [`ranked suggestions`](#method.ranked_suggestions) in the background, without blocking
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Maximum number of items to return
+    /// * `ranking` - How to interleave history and remote suggestions
+    /// * `debounce` - How long to wait for the query to settle before fetching
+    #[must_use]
+    pub fn fetcher(
+        &self,
+        count: usize,
+        ranking: suggestion::Ranking,
+        debounce: std::time::Duration,
+    ) -> fetcher::Fetcher {
+        let executor = self.clone();
+        fetcher::Fetcher::spawn(
+            Box::new(move |query| executor.ranked_suggestions(query, count, ranking).ok()),
+            debounce,
+        )
+    }
+
     /// Queries the suggestion API for this executor for suggestions
     ///
     /// # Arguments
@@ -167,50 +576,272 @@ impl Executor {
     ///
     /// * If response is not 200 OK, then [`Error(Fetch)`](../error/struct.Error.html)
     /// * If response cannot be parsed, then [`Error(Parse)`](../error/struct.Error.html)
+    ///
+    /// # See also
+    /// [`remote_suggestions(query)`](#method.remote_suggestions)
     pub fn suggest(&self, query: &str) -> Result<Vec<String>> {
-        if query.len() < 3 || self.suggestion.is_empty() || self.parser == parser::Parser::None {
-            return Ok(vec![]);
+        self.remote_suggestions(query)
+            .map(|suggestions| suggestions.into_iter().map(Suggestion::into_text).collect())
+    }
+
+    /// Queries the suggestion API for this executor for
+    /// [`Suggestions`](../suggestion/struct.Suggestion.html), scored by the order given by the API
+    ///
+    /// Responses are kept in the [`cache`](../cache/index.html), so repeated queries are answered
+    /// without reaching the network. If the suggestion API cannot be used, previously cached
+    /// suggestions are returned, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query string to to get suggestions for
+    ///
+    /// # Errors
+    ///
+    /// * If [offline](#method.is_offline) and nothing was cached, then [`Error(Offline)`](../error/struct.Error.html)
+    /// * If response is not 200 OK, then [`Error(Fetch)`](../error/struct.Error.html)
+    /// * If response cannot be parsed, then [`Error(Parse)`](../error/struct.Error.html)
+    pub fn remote_suggestions(&self, query: &str) -> Result<Vec<Suggestion>> {
+        match self.remote(query) {
+            (suggestions, suggestion::Status::Offline) if suggestions.is_empty() => {
+                Err(error::Error::Offline)
+            }
+            (suggestions, suggestion::Status::Failed(error)) if suggestions.is_empty() => {
+                Err(error)
+            }
+            (suggestions, _) => Ok(suggestions),
+        }
+    }
+
+    fn remote(&self, query: &str) -> (Vec<Suggestion>, suggestion::Status) {
+        if !self.accepts(query) {
+            return (vec![], suggestion::Status::Skipped);
+        }
+
+        // Local sources are neither cached nor affected by offline mode
+        let local = match &self.source {
+            Source::Http if self.suggestion.is_empty() || self.parser == parser::Parser::None => {
+                return (vec![], suggestion::Status::Skipped)
+            }
+            Source::Process(_) if self.parser == parser::Parser::None => {
+                return (vec![], suggestion::Status::Skipped)
+            }
+            Source::Http => None,
+            Source::Process(process) => Some(self.run(process, query)),
+            Source::WordList(list) => Some(list.suggestions(&self.name, query)),
+        };
+        if let Some(result) = local {
+            return match result {
+                Ok(suggestions) => (suggestions, suggestion::Status::Fetched),
+                Err(error) => (vec![], suggestion::Status::Failed(error)),
+            };
         }
 
+        let fingerprint = self.fingerprint();
+        if let Some(cached) = cache::get(&self.name, fingerprint, query) {
+            return (cached, suggestion::Status::Cached);
+        }
+
+        let status = if http::is_offline_for(&self.suggestion) {
+            suggestion::Status::Offline
+        } else {
+            match self.fetch(query) {
+                Ok(suggestions) => return (suggestions, suggestion::Status::Fetched),
+                Err(error) => suggestion::Status::Failed(error),
+            }
+        };
+
+        (
+            cache::get_stale(&self.name, fingerprint, query).unwrap_or_default(),
+            status,
+        )
+    }
+
+    fn run(&self, process: &process::Process, query: &str) -> Result<Vec<Suggestion>> {
+        let result = process.run(query, self.charset.as_deref())?;
+        parser::parse(&self.parser, &self.parsers, &result)
+    }
+
+    fn fetch(&self, query: &str) -> Result<Vec<Suggestion>> {
         let result = {
-            let response = ureq::get(format!("{}{}", self.suggestion, query).as_str()).call()?;
-            response
-                .into_string()
-                .map_err(error::Parse::from)
-                .map_err(error::Error::Parse)?
+            let response = http::get(format!("{}{}", self.suggestion, query).as_str(), &self.http)?;
+            http::body(response, self.charset.as_deref())?
         };
 
-        parser::parse(&self.parser, &result)
+        let suggestions = parser::parse(&self.parser, &self.parsers, &result)?;
+        // Failing to cache should not prevent the suggestions from being used
+        cache::put(&self.name, self.fingerprint(), query, &suggestions).ok();
+        Ok(suggestions)
+    }
+
+    /// Identifies the settings that suggestions depend on, so cached ones are not reused once
+    /// they change
+    fn fingerprint(&self) -> u64 {
+        cache::fingerprint(&(
+            &self.suggestion,
+            &self.parser,
+            &self.charset,
+            &self.prefix,
+            &self.suffix,
+        ))
     }
 }
 
 /// Contains all [targets](struct.Executor.html) known
 ///
-/// This is the representation of the configuration that gets serialized and deserialized
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-pub struct Executors(Vec<Executor>);
+/// This is the representation of the configuration that gets serialized and deserialized, and is
+/// saved as a JSON array of targets
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Debug, Default)]
+#[serde(transparent)]
+pub struct Executors {
+    executors: Vec<Executor>,
+    #[serde(skip)]
+    parsers: parser::Registry,
+}
 
-/// Loads a instance of [`Executors`](struct.Executors.html) based on `path`
+impl PartialEq for Executors {
+    fn eq(&self, other: &Self) -> bool {
+        self.executors == other.executors
+    }
+}
+
+impl<'a> IntoIterator for &'a Executors {
+    type Item = &'a Executor;
+    type IntoIter = std::slice::Iter<'a, Executor>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Loads a instance of [`Executors`](struct.Executors.html) from the default path
 ///
-/// # Arguments
+/// Besides the saved configuration, every JSON, TOML or YAML file in the `targets.d` directory of
+/// the default path is loaded, in file name order, and its targets are added after the saved ones.
+/// Each target remembers the file it came from, as its [`origin()`](struct.Executor.html#method.origin)
 ///
-/// * `path` - Path from where to load [`Executors`](struct.Executors.html)
+/// The saved targets are not [validated](struct.Executors.html#method.validate) again, so a target
+/// saved by an earlier version keeps working even if it breaks a newer rule
 ///
 /// # Errors
 ///
 /// * If the path for the configuration cannot be created, then [`Error(Path)`](../error/struct.Error.html)
-/// * If default path cannot be read, then [`Error(Read)`](../error/struct.Error.html)
+/// * If the saved configuration cannot be read, or is missing and there are no drop-in files, then [`Error(Read)`](../error/struct.Error.html)
 /// * If the configuration cannot be deserialized, then [`Error(Deserialize)`](../error/struct.Error.html)
+/// * If a drop-in file is not a valid configuration, then [`Error(Include)`](../error/struct.Error.html)
+/// * If a target name is defined more than once, then [`Error(Invalid)`](../error/struct.Error.html)
 ///
 /// # See also
-/// [`load_default()`](fn.load_default.html)
+/// [`load_saved()`](fn.load_saved.html)
 pub fn load_default() -> Result<Executors> {
-    default_path()
-        .map(|path| path.join(CONFIG_FILE))
-        .and_then(load)
+    default_path().and_then(|path| load_in(&path))
 }
 
-fn default_path() -> Result<std::path::PathBuf> {
+/// Loads only the saved configuration from the default path, without the drop-in files of
+/// [`load_default()`](fn.load_default.html)
+///
+/// # Errors
+///
+/// * If the path for the configuration cannot be created, then [`Error(Path)`](../error/struct.Error.html)
+/// * If default path cannot be read, then [`Error(Read)`](../error/struct.Error.html)
+/// * If the configuration cannot be deserialized, then [`Error(Deserialize)`](../error/struct.Error.html)
+pub fn load_saved() -> Result<Executors> {
+    config_path().and_then(load)
+}
+
+fn load_in(directory: &std::path::Path) -> Result<Executors> {
+    let saved = load(directory.join(CONFIG_FILE));
+    let bases = saved.as_ref().map_or(&[][..], |saved| saved.executors());
+    let includes = load_includes(&directory.join(INCLUDE_DIR), bases)?;
+    let saved = match saved {
+        Ok(saved) => saved.into_vec(),
+        Err(error::Error::Read(_, e))
+            if e.kind() == std::io::ErrorKind::NotFound && !includes.is_empty() =>
+        {
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+    merge(saved, includes)
+}
+
+/// Loads the targets of every configuration file in `directory`, in file name order
+///
+/// Targets may extend the ones in `bases` and in the files before their own
+fn load_includes(directory: &std::path::Path, bases: &[Executor]) -> Result<Vec<Executor>> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(error::Error::Read(directory.into(), e)),
+    };
+
+    let mut files = entries
+        .filter_map(std::result::Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .and_then(std::ffi::OsStr::to_str)
+                .is_some_and(|name| !name.starts_with('.'))
+        })
+        .filter_map(|path| {
+            let format = path.extension()?.to_str()?.parse::<Format>().ok()?;
+            Some((path, format))
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut executors = Vec::new();
+    for (path, format) in files {
+        let config =
+            std::fs::read_to_string(&path).map_err(|e| error::Error::Read(path.clone(), e))?;
+        let known = bases.iter().chain(&executors).cloned().collect::<Vec<_>>();
+        let loaded = from_format_extending(&config, format, &known)
+            .map_err(|e| error::Error::Include(path.clone(), Box::new(e)))?;
+        executors.extend(loaded.into_vec().into_iter().map(|executor| Executor {
+            origin: DropIn(Some(path.clone())),
+            ..executor
+        }));
+    }
+    Ok(executors)
+}
+
+/// Adds the drop-in targets in `includes` after the `saved` ones, reporting names defined twice
+///
+/// Both were validated when they were loaded, so no other problem is reported here
+fn merge(saved: Vec<Executor>, includes: Vec<Executor>) -> Result<Executors> {
+    let mut executors = saved;
+    let mut problems = Vec::new();
+    for executor in includes {
+        if let Some((index, other)) = executors
+            .iter()
+            .enumerate()
+            .find(|(_, other)| other.name == executor.name)
+        {
+            problems.push(
+                validation::Problem::new(
+                    "name",
+                    format!("Defined in both {} and {}", other.origin, executor.origin),
+                    Some(String::from("rename or remove one of them")),
+                )
+                .in_target(index, &executor.name),
+            );
+        } else {
+            executors.push(executor);
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(Executors::new(executors))
+    } else {
+        Err(error::Error::Invalid(problems))
+    }
+}
+
+pub(crate) fn config_path() -> Result<std::path::PathBuf> {
+    default_path().map(|path| path.join(CONFIG_FILE))
+}
+
+pub(crate) fn default_path() -> Result<std::path::PathBuf> {
     std::env::var("VAI_CONFIG")
         .map(std::path::PathBuf::from)
         .or_else(|_| match dirs::config_dir() {
@@ -221,6 +852,9 @@ fn default_path() -> Result<std::path::PathBuf> {
 
 /// Loads a instance of [`Executors`](struct.Executors.html) based on `path`
 ///
+/// Configurations are saved as JSON. Those saved in the binary layout of earlier versions are
+/// still loaded, and saved as JSON the next time they are saved
+///
 /// # Arguments
 ///
 /// * `path` - Path from where to load [`Executors`](struct.Executors.html)
@@ -233,37 +867,327 @@ fn default_path() -> Result<std::path::PathBuf> {
 /// # See also
 /// [`load_default()`](fn.load_default.html)
 pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Executors> {
-    bincode::deserialize(
-        std::fs::read(&path)
-            .map_err(|e| error::Error::Read(path.as_ref().into(), e))?
-            .as_slice(),
-    )
-    .map(Executors)
-    .map_err(error::Deserialize::from)
+    let bytes = std::fs::read(&path).map_err(|e| error::Error::Read(path.as_ref().into(), e))?;
+    decode(&bytes).map(Executors::new)
+}
+
+/// A target as saved by earlier versions, which stored the configuration with bincode
+///
+/// Bincode is not self-describing, so this layout is fixed and can never gain fields
+#[derive(Deserialize)]
+struct BinaryExecutor {
+    name: String,
+    alias: String,
+    command: String,
+    suggestion: String,
+    parser: parser::Parser,
+}
+
+impl From<BinaryExecutor> for Executor {
+    fn from(executor: BinaryExecutor) -> Self {
+        Self {
+            name: executor.name,
+            alias: executor.alias,
+            command: executor.command,
+            suggestion: executor.suggestion,
+            parser: executor.parser,
+            http: http::Settings::default(),
+            min_length: DEFAULT_MIN_LENGTH,
+            max_length: None,
+            suggest_blank: false,
+            charset: None,
+            source: Source::default(),
+            extends: None,
+            prefix: String::new(),
+            suffix: String::new(),
+            declared: None,
+            parsers: parser::Registry::default(),
+            origin: DropIn::default(),
+        }
+    }
+}
+
+/// Reads the targets of a saved configuration, either as JSON or in the binary layout
+///
+/// Derived targets are saved as written, so they are resolved against the other saved targets
+fn decode(bytes: &[u8]) -> Result<Vec<Executor>> {
+    if let Ok(values) = serde_json::from_slice::<Vec<serde_json::Value>>(bytes) {
+        if inheritance::uses_extends(&values) {
+            return format::from_values(&values, &[]).map_err(error::Error::Invalid);
+        }
+    }
+
+    match serde_json::from_slice(bytes) {
+        Ok(executors) => Ok(executors),
+        Err(json) => match bincode::deserialize::<Vec<BinaryExecutor>>(bytes) {
+            Ok(executors) => Ok(executors.into_iter().map(Executor::from).collect()),
+            Err(_) if bytes.trim_ascii_start().starts_with(b"[") => {
+                Err(error::Deserialize::from(json))
+            }
+            Err(binary) => Err(error::Deserialize::from(binary)),
+        },
+    }
     .map_err(error::Error::Deserialize)
 }
 
-/// Creates a new [`Executors`](struct.Executors.html) based on the json provided through `std::io::stdin`
+/// Creates a new [`Executors`](struct.Executors.html) based on the configuration provided through
+/// `std::io::stdin`, in any [format](../format/enum.Format.html)
 ///
-/// This is useful for generating new configuration by json by calling `load_from_stdin()` followed
+/// This is useful for generating new configuration by calling `load_from_stdin()` followed
 /// by [`Executors::save_default()`](struct.Executors.html#method.save_default)
 ///
 /// # Errors
 ///
-/// If the json provided cannot be deserialized, then [`Error(Deserialize)`](../error/struct.Error.html)
+/// * If stdin cannot be read, then [`Error(Read)`](../error/struct.Error.html)
+/// * If the configuration provided is not valid, then [`Error(Invalid)`](../error/struct.Error.html)
+///
+/// # See also
+/// [`from_text(config)`](fn.from_text.html)
 pub fn load_from_stdin() -> Result<Executors> {
-    let executors: Vec<Executor> = serde_json::from_reader(std::io::stdin())
-        .map_err(error::Deserialize::from)
-        .map_err(error::Error::Deserialize)?;
-    Ok(Executors(
-        executors.into_iter().map(Executor::clean_up_name).collect(),
-    ))
+    load_from_stdin_extending(&Executors::default())
+}
+
+/// Same as [`load_from_stdin()`](fn.load_from_stdin.html), but targets may also extend the ones
+/// in `bases`
+///
+/// # Errors
+///
+/// * If stdin cannot be read, then [`Error(Read)`](../error/struct.Error.html)
+/// * If the configuration provided is not valid, then [`Error(Invalid)`](../error/struct.Error.html)
+///
+/// # See also
+/// [`from_text_extending(config, bases)`](fn.from_text_extending.html)
+pub fn load_from_stdin_extending(bases: &Executors) -> Result<Executors> {
+    use std::io::Read;
+
+    let mut config = String::new();
+    std::io::stdin()
+        .read_to_string(&mut config)
+        .map_err(|e| error::Error::Read(std::path::PathBuf::from("stdin"), e))?;
+    from_text_extending(&config, bases)
+}
+
+/// Returns the [JSON Schema](https://json-schema.org) of the configuration, as pretty printed json
+///
+/// The schema is generated from the same types the configuration is deserialized into. Names of
+/// parsers and other options are listed in their upper case form, although any case is accepted.
+/// Targets declaring `extends` only require a `name`, since they may inherit every other field
+///
+/// # Errors
+///
+/// * If the schema cannot be serialized, then [`Error(Serialize)`](../error/struct.Error.html)
+pub fn json_schema() -> Result<String> {
+    let mut schema = schemars::schema_for!(Executors);
+    schema.schema.metadata().title = Some(String::from("vai configuration"));
+    if let Some(schemars::schema::Schema::Object(executor)) = schema.definitions.get_mut("Executor")
+    {
+        let object = executor.object();
+        let inheritable = object
+            .required
+            .iter()
+            .filter(|field| *field != "name")
+            .cloned()
+            .collect();
+        object.required.retain(|field| field == "name");
+
+        let required = |required| {
+            schemars::schema::Schema::Object(schemars::schema::SchemaObject {
+                object: Some(Box::new(schemars::schema::ObjectValidation {
+                    required,
+                    ..Default::default()
+                })),
+                ..Default::default()
+            })
+        };
+        let subschemas = executor.subschemas();
+        subschemas.if_schema = Some(Box::new(required(
+            std::iter::once(String::from("extends")).collect(),
+        )));
+        subschemas.else_schema = Some(Box::new(required(inheritable)));
+    }
+    serde_json::to_string_pretty(&schema)
+        .map_err(error::Serialize::from)
+        .map_err(error::Error::Serialize)
+}
+
+/// Creates a new [`Executors`](struct.Executors.html) based on `json`, after
+/// [validating](struct.Executors.html#method.validate) it
+///
+/// # Arguments
+///
+/// * `json` - The configuration, as a json array of targets
+///
+/// # Errors
+///
+/// If `json` is not a valid configuration, then [`Error(Invalid)`](../error/struct.Error.html)
+/// with every [problem](../validation/struct.Problem.html) found, along with its line and column
+///
+/// # See also
+/// [`from_format(config, format)`](fn.from_format.html)
+pub fn from_json(json: &str) -> Result<Executors> {
+    from_format(json, Format::Json)
+}
+
+/// Creates a new [`Executors`](struct.Executors.html) based on `config`, whose
+/// [format](../format/enum.Format.html#method.detect) is detected from its content
+///
+/// # Arguments
+///
+/// * `config` - The configuration, as JSON, TOML or YAML
+///
+/// # Errors
+///
+/// If `config` is not a valid configuration, then [`Error(Invalid)`](../error/struct.Error.html)
+/// with every [problem](../validation/struct.Problem.html) found
+///
+/// # See also
+/// [`from_format(config, format)`](fn.from_format.html)
+pub fn from_text(config: &str) -> Result<Executors> {
+    from_format(config, Format::detect(config))
+}
+
+/// Same as [`from_text(config)`](fn.from_text.html), but targets may also extend the ones in
+/// `bases`, such as the targets in use when `config` is imported
+///
+/// # Errors
+///
+/// If `config` is not a valid configuration, then [`Error(Invalid)`](../error/struct.Error.html)
+/// with every [problem](../validation/struct.Problem.html) found
+pub fn from_text_extending(config: &str, bases: &Executors) -> Result<Executors> {
+    from_format_extending(config, Format::detect(config), bases.executors())
+}
+
+/// Creates a new [`Executors`](struct.Executors.html) based on `config`, after
+/// [validating](struct.Executors.html#method.validate) it
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+/// * `format` - The [format](../format/enum.Format.html) of `config`
+///
+/// # Errors
+///
+/// If `config` is not a valid configuration, then [`Error(Invalid)`](../error/struct.Error.html)
+/// with every [problem](../validation/struct.Problem.html) found. Problems in JSON configurations
+/// carry their line and column, as do syntax errors in the other formats
+pub fn from_format(config: &str, format: Format) -> Result<Executors> {
+    from_format_extending(config, format, &[])
+}
+
+/// Same as [`from_format(config, format)`](fn.from_format.html), but targets may also extend the
+/// ones in `bases`
+fn from_format_extending(config: &str, format: Format, bases: &[Executor]) -> Result<Executors> {
+    let executors = format::deserialize(config, format, bases)?;
+    let executors = Executors::new(executors.into_iter().map(Executor::clean_up_name).collect());
+
+    let problems = executors.validate();
+    if problems.is_empty() {
+        Ok(executors)
+    } else if format == Format::Json {
+        Err(error::Error::Invalid(validation::locate(config, problems)))
+    } else {
+        Err(error::Error::Invalid(problems))
+    }
 }
 
 impl Executors {
+    /// Shares a single parser registry between all `executors`
+    pub(crate) fn new(executors: Vec<Executor>) -> Self {
+        let parsers = parser::Registry::default();
+        Self {
+            executors: executors
+                .into_iter()
+                .map(|executor| Executor {
+                    parsers: parsers.clone(),
+                    ..executor
+                })
+                .collect(),
+            parsers,
+        }
+    }
+
     #[inline]
     fn executors(&self) -> &Vec<Executor> {
-        &self.0
+        &self.executors
+    }
+
+    /// Returns an iterator over all the [`targets`](struct.Executor.html), in order
+    pub fn iter(&self) -> std::slice::Iter<'_, Executor> {
+        self.executors.iter()
+    }
+
+    /// Returns the targets that were not loaded from a drop-in file
+    fn saved(&self) -> impl Iterator<Item = &Executor> {
+        self.executors
+            .iter()
+            .filter(|executor| executor.origin.0.is_none())
+    }
+
+    #[inline]
+    pub(crate) fn into_vec(self) -> Vec<Executor> {
+        self.executors
+    }
+
+    /// Makes `parser` available to every [`target`](struct.Executor.html) whose configuration
+    /// references `name` as `{"Custom": "<name>"}`
+    ///
+    /// Names are case insensitive and replace any parser previously registered with the same name,
+    /// including the built-in ones
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name to reference the parser by
+    /// * `parser` - The [parser](../parser/trait.Parse.html)
+    pub fn register_parser<P: parser::Parse + 'static>(&self, name: &str, parser: P) {
+        self.parsers.register(name, parser);
+    }
+
+    /// Returns the [registry](../parser/struct.Registry.html) of parsers shared by all
+    /// [`targets`](struct.Executor.html)
+    #[must_use]
+    pub fn parsers(&self) -> &parser::Registry {
+        &self.parsers
+    }
+
+    /// Checks every [`target`](struct.Executor.html) for mistakes that deserialization cannot catch
+    ///
+    /// These include empty or non-http URLs, suggestion settings that are never used, invalid
+    /// patterns and names that clash with other targets. Every problem found is returned
+    #[must_use]
+    pub fn validate(&self) -> Vec<validation::Problem> {
+        let mut problems = Vec::new();
+        for (index, executor) in self.executors().iter().enumerate() {
+            let mut clashes = Vec::new();
+            let earlier = &self.executors()[..index];
+            if earlier.iter().any(|other| other.name == executor.name) {
+                clashes.push(validation::Problem::new(
+                    "name",
+                    "Another target has the same name, so this one cannot be used",
+                    Some(String::from("rename one of the targets")),
+                ));
+            }
+            if !executor.alias.is_empty()
+                && (self
+                    .executors()
+                    .iter()
+                    .any(|other| other.name == executor.alias)
+                    || earlier.iter().any(|other| other.alias == executor.alias))
+            {
+                clashes.push(validation::Problem::new(
+                    "alias",
+                    "Another target already answers to this alias",
+                    Some(String::from("use a different alias, or remove it")),
+                ));
+            }
+
+            problems.extend(
+                clashes
+                    .into_iter()
+                    .chain(executor.problems())
+                    .map(|problem| problem.in_target(index, &executor.name)),
+            );
+        }
+        problems
     }
 
     /// Returns all the [`targets`](struct.Executor.html) for querying
@@ -277,18 +1201,37 @@ impl Executors {
 
     /// Saves this `Executor` to disk in the default path
     ///
+    /// Targets loaded from drop-in files are left to their files. The remaining targets are checked
+    /// against the drop-in files first, so that no name ends up defined twice
+    ///
     /// # Errors
     ///
     /// * If the path for the configuration cannot be created, then [`Error(Path)`](../error/struct.Error.html)
     /// * If default path cannot be written, then [`Error(Write)`](../error/struct.Error.html)
     /// * If the configuration cannot be serialized, then [`Error(Serialize)`](../error/struct.Error.html)
+    /// * If a drop-in file is not a valid configuration, then [`Error(Include)`](../error/struct.Error.html)
+    /// * If a target name is also defined in a drop-in file, then [`Error(Invalid)`](../error/struct.Error.html)
     ///
     /// # See also
     /// [`save(path)`](#method.save)
     pub fn save_default(&self) -> Result {
-        default_path()
-            .map(|path| path.join(CONFIG_FILE))
-            .and_then(|path| self.save(path))
+        self.check_default()?;
+        config_path().and_then(|path| self.save(path))
+    }
+
+    /// Checks that this configuration can be [saved](#method.save_default) to the default path,
+    /// without clashing with the drop-in files there
+    ///
+    /// # Errors
+    ///
+    /// * If the path for the configuration cannot be created, then [`Error(Path)`](../error/struct.Error.html)
+    /// * If a drop-in file is not a valid configuration, then [`Error(Include)`](../error/struct.Error.html)
+    /// * If a target name is also defined in a drop-in file, then [`Error(Invalid)`](../error/struct.Error.html)
+    pub fn check_default(&self) -> Result {
+        let saved = self.saved().cloned().collect::<Vec<_>>();
+        let includes =
+            default_path().and_then(|path| load_includes(&path.join(INCLUDE_DIR), &saved))?;
+        merge(saved, includes).map(|_| ())
     }
 
     /// Saves this `Executor` to disk in the default path
@@ -297,21 +1240,26 @@ impl Executors {
     ///
     /// * `path` - Path where to save [`Executors`](struct.Executors.html)
     ///
+    /// Targets loaded from drop-in files are not saved, and derived targets are saved as written.
+    /// If `path` already holds a different configuration, it is first kept as a
+    /// [backup](../backup/index.html)
+    ///
     /// # Errors
     ///
     /// * If the path for the configuration cannot be created, then [`Error(Path)`](../error/struct.Error.html)
     /// * If default path cannot be written, then [`Error(Write)`](../error/struct.Error.html)
     /// * If the configuration cannot be serialized, then [`Error(Serialize)`](../error/struct.Error.html)
+    /// * If a saved target extends one that is not saved, then [`Error(Invalid)`](../error/struct.Error.html)
     ///
     /// # See also
     /// [`save(path)`](#method.save_default)
     pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result {
         if let Some(parent) = path.as_ref().parent() {
-            std::fs::create_dir_all(&parent).map_err(|e| error::Error::Write(parent.into(), e))?;
+            std::fs::create_dir_all(parent).map_err(|e| error::Error::Write(parent.into(), e))?;
         }
-        let bytes = bincode::serialize(self.executors())
-            .map_err(error::Serialize::from)
-            .map_err(error::Error::Serialize)?;
+        let bytes = format::serialize(self.saved(), Format::Json)?.into_bytes();
+        decode(&bytes)?;
+        backup::create(path.as_ref(), &bytes)?;
         std::fs::write(&path, bytes).map_err(|e| error::Error::Write(path.as_ref().into(), e))
     }
 
@@ -320,10 +1268,24 @@ impl Executors {
     /// # Errors
     ///
     /// * If the configuration cannot be serialized, then [`Error(Serialize)`](../error/struct.Error.html)
+    ///
+    /// # See also
+    /// [`to_format(format)`](#method.to_format)
     pub fn to_json(&self) -> Result<String> {
-        serde_json::to_string_pretty(&self)
-            .map_err(error::Serialize::from)
-            .map_err(error::Error::Serialize)
+        self.to_format(Format::Json)
+    }
+
+    /// Output this `Executor` in `format`
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The [format](../format/enum.Format.html) to output
+    ///
+    /// # Errors
+    ///
+    /// * If the configuration cannot be serialized, then [`Error(Serialize)`](../error/struct.Error.html)
+    pub fn to_format(&self, format: Format) -> Result<String> {
+        format::serialize(self.iter(), format)
     }
 
     /// Get the target that matches the provided `name`
@@ -343,12 +1305,515 @@ impl Executors {
         }
 
         // Then try aliases
-        for executor in self.executors() {
-            if executor.alias == lower_case_name {
-                return Some(executor);
+        self.executors()
+            .iter()
+            .find(|executor| executor.alias == lower_case_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn executor(rules: &str) -> Executor {
+        serde_json::from_str(&format!(
+            r#"{{
+                "name": "test",
+                "alias": "t",
+                "command": "https://example.com/?q=",
+                "suggestion": "https://example.com/suggest?q=",
+                "parser": "Google"
+                {rules}
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bundled_config() {
+        let executors = from_json(include_str!("../config.json")).unwrap();
+        assert!(executors
+            .executors()
+            .iter()
+            .any(|executor| executor.parser == parser::Parser::Google));
+    }
+
+    #[test]
+    fn test_load_binary_configuration() {
+        let directory = crate::testing::directory("binary");
+        let config = directory.join(CONFIG_FILE);
+        // Saved by a version that stored the configuration with bincode
+        std::fs::write(&config, include_bytes!("../config")).unwrap();
+
+        let executors = load(&config).unwrap();
+        assert_eq!(
+            executors.list_targets(),
+            ["start", "google", "duck", "youtube", "image"]
+        );
+        let duck = executors.find("duck").unwrap();
+        assert_eq!(duck.command(), "https://duckduckgo.com/?q=");
+        assert_eq!(duck.suggestion, "https://duckduckgo.com/ac/?q=");
+        assert_eq!(duck.parser, parser::Parser::Duck);
+        assert_eq!(duck.min_length, DEFAULT_MIN_LENGTH);
+        assert_eq!(
+            executors.find("youtube").unwrap().parser,
+            parser::Parser::None
+        );
+
+        executors.save(&config).unwrap();
+        assert!(std::fs::read(&config).unwrap().starts_with(b"["));
+        assert_eq!(load(&config).unwrap(), executors);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_load_corrupted_configuration() {
+        assert!(matches!(
+            decode(b"[{\"name\": \"google\"}]"),
+            Err(error::Error::Deserialize(error::Deserialize::Json(_)))
+        ));
+        assert!(matches!(
+            decode(&[1, 2, 3]),
+            Err(error::Error::Deserialize(error::Deserialize::Binary(_)))
+        ));
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema: serde_json::Value = serde_json::from_str(&json_schema().unwrap()).unwrap();
+        assert_eq!(schema["type"], "array");
+        assert_eq!(schema["items"]["$ref"], "#/definitions/Executor");
+
+        let executor = &schema["definitions"]["Executor"];
+        assert_eq!(executor["required"], serde_json::json!(["name"]));
+        assert_eq!(executor["if"]["required"], serde_json::json!(["extends"]));
+        assert_eq!(
+            executor["else"]["required"],
+            serde_json::json!(["alias", "command", "parser", "suggestion"])
+        );
+        assert_eq!(executor["properties"]["min_length"]["default"], 3);
+        assert!(executor["properties"].get("parsers").is_none());
+
+        let parsers = serde_json::to_string(&schema["definitions"]["Parser"]).unwrap();
+        for name in ["GOOGLE", "DUCK", "OPEN_SEARCH", "JSONP", "CUSTOM", "NONE"] {
+            assert!(parsers.contains(&format!("\"{name}\"")), "{name}");
+        }
+    }
+
+    fn problems(json: &str) -> Vec<validation::Problem> {
+        match from_json(json) {
+            Err(error::Error::Invalid(problems)) => problems,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validation_gathers_every_problem() {
+        let problems = problems(
+            r#"[
+  {
+    "name": "google",
+    "alias": "g",
+    "command": "",
+    "suggestion": "https://www.google.com/complete/search?client=chrome&q=",
+    "parser": "NONE"
+  },
+  {
+    "name": "intranet",
+    "alias": "g",
+    "command": "ftp://intranet/?q=",
+    "suggestion": "",
+    "parser": { "REGEX": { "pattern": "(" } },
+    "min_length": 4,
+    "max_length": 2
+  }
+]"#,
+        );
+
+        let found = problems
+            .iter()
+            .map(|problem| {
+                (
+                    problem.target().unwrap(),
+                    problem.field(),
+                    problem.line().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("google", "command", 5),
+                ("google", "suggestion", 6),
+                ("intranet", "alias", 11),
+                ("intranet", "command", 12),
+                ("intranet", "parser", 14),
+                ("intranet", "parser.REGEX.pattern", 14),
+                ("intranet", "max_length", 16),
+            ]
+        );
+        assert!(problems
+            .iter()
+            .all(|problem| problem.fix().is_some() || problem.field() == "parser.REGEX.pattern"));
+    }
+
+    #[test]
+    fn test_validation_reports_deserialization_errors() {
+        let problems = problems(
+            r#"[
+  {
+    "name": "google",
+    "alias": "g",
+    "command": "https://www.google.com/search?q=",
+    "suggestion": "https://www.google.com/complete/search?client=chrome&q=",
+    "parser": "BING"
+  }
+]"#,
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].target(), Some("google"));
+        assert_eq!(problems[0].field(), "parser");
+        assert_eq!(problems[0].line(), Some(7));
+        assert!(problems[0].message().contains("`GOOGLE`"));
+    }
+
+    #[test]
+    fn test_fingerprint_follows_suggestion_settings() {
+        let fingerprint = executor("").fingerprint();
+        assert_eq!(executor(r#", "min_length": 1"#).fingerprint(), fingerprint);
+        assert_ne!(
+            executor(r#", "charset": "latin1""#).fingerprint(),
+            fingerprint
+        );
+        assert_ne!(
+            executor(r#", "suffix": "&hl=de""#).fingerprint(),
+            fingerprint
+        );
+        let duck = Executor {
+            parser: parser::Parser::Duck,
+            ..executor("")
+        };
+        assert_ne!(duck.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_default_min_length() {
+        let executor = executor("");
+        assert!(!executor.accepts("ab"));
+        assert!(executor.accepts("abc"));
+        assert!(!executor.accepts("  ab  "));
+    }
+
+    #[test]
+    fn test_length_counts_graphemes() {
+        let executor = executor("");
+        assert!(!executor.accepts("日本"));
+        assert!(executor.accepts("日本語"));
+        assert!(!executor.accepts("e\u{301}e\u{301}"));
+        assert!(executor.accepts("e\u{301}e\u{301}e\u{301}"));
+    }
+
+    #[test]
+    fn test_custom_lengths() {
+        let executor = executor(r#", "min_length": 1, "max_length": 4"#);
+        assert!(executor.accepts("日"));
+        assert!(executor.accepts("rust"));
+        assert!(!executor.accepts("rusty"));
+    }
+
+    #[test]
+    fn test_blank_queries() {
+        assert!(!executor(r#", "min_length": 0"#).accepts("   "));
+        assert!(executor(r#", "min_length": 0, "suggest_blank": true"#).accepts("   "));
+        assert!(executor(r#", "min_length": 0"#).accepts(""));
+    }
+
+    #[test]
+    fn test_registered_parsers_are_shared() {
+        let executors = Executors::new(vec![executor(""), executor("")]);
+        executors.register_parser("mine", parser::Parser::Lines);
+        assert!(executors
+            .executors()
+            .iter()
+            .all(|executor| executor.parsers.get("MINE").is_some()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_source() {
+        let executor = executor(
+            r#", "source": {"Process": {
+                "program": "sh",
+                "args": ["-c", "printf '[\"%s\",[\"%s one\",\"%s two\"]]' \"$1\" \"$1\" \"$1\"", "sh"]
+            }}"#,
+        );
+        let suggestions = executor.remote_suggestions("rust").unwrap();
+        assert_eq!(suggestions[0].text(), "rust one");
+        assert_eq!(suggestions[1].text(), "rust two");
+    }
+
+    fn drop_in_directory(name: &str) -> std::path::PathBuf {
+        let directory = crate::testing::directory(&format!("drop_in_{name}"));
+        std::fs::create_dir_all(directory.join(INCLUDE_DIR)).unwrap();
+        from_json(
+            r#"[{"name": "google", "alias": "g", "command": "https://google.com/?q=",
+                 "suggestion": "", "parser": "NONE"}]"#,
+        )
+        .unwrap()
+        .save(directory.join(CONFIG_FILE))
+        .unwrap();
+        directory
+    }
+
+    fn drop_in(directory: &std::path::Path, file: &str, content: &str) {
+        std::fs::write(directory.join(INCLUDE_DIR).join(file), content).unwrap();
+    }
+
+    #[test]
+    fn test_drop_in_files() {
+        let directory = drop_in_directory("merge");
+        drop_in(
+            &directory,
+            "20-dev.json",
+            r#"[{"name": "rust", "alias": "", "command": "https://docs.rs/?q=",
+                 "suggestion": "", "parser": "NONE"}]"#,
+        );
+        drop_in(
+            &directory,
+            "10-work.toml",
+            "[[target]]\nname = \"jira\"\nalias = \"j\"\ncommand = \"https://jira/?q=\"\n\
+             suggestion = \"\"\nparser = \"NONE\"\n",
+        );
+        drop_in(&directory, "README.md", "Not a configuration");
+
+        let executors = load_in(&directory).unwrap();
+        assert_eq!(executors.list_targets(), ["google", "jira", "rust"]);
+        let origins = executors
+            .iter()
+            .map(|executor| executor.origin().and_then(std::path::Path::file_name))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            origins,
+            [
+                None,
+                Some("10-work.toml".as_ref()),
+                Some("20-dev.json".as_ref())
+            ]
+        );
+
+        // Drop-in targets stay in their files
+        executors.save(directory.join(CONFIG_FILE)).unwrap();
+        assert_eq!(
+            load(directory.join(CONFIG_FILE)).unwrap().list_targets(),
+            ["google"]
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_configuration_with_drop_ins() {
+        let directory = drop_in_directory("unreadable");
+        drop_in(
+            &directory,
+            "mine.json",
+            r#"[{"name": "mine", "alias": "", "command": "https://mine.org/?q=",
+                 "suggestion": "", "parser": "NONE"}]"#,
+        );
+        let config = directory.join(CONFIG_FILE);
+        std::fs::remove_file(&config).unwrap();
+        assert_eq!(load_in(&directory).unwrap().list_targets(), ["mine"]);
+
+        std::fs::create_dir(&config).unwrap();
+        assert!(matches!(
+            load_in(&directory),
+            Err(error::Error::Read(path, _)) if path == config
+        ));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_drop_in_duplicates() {
+        let directory = drop_in_directory("duplicates");
+        drop_in(
+            &directory,
+            "work.json",
+            r#"[{"name": "google", "alias": "", "command": "https://google.com/?q=",
+                 "suggestion": "", "parser": "NONE"}]"#,
+        );
+
+        match load_in(&directory) {
+            Err(error::Error::Invalid(problems)) => {
+                assert_eq!(problems.len(), 1);
+                assert_eq!(problems[0].target(), Some("google"));
+                assert!(problems[0]
+                    .message()
+                    .starts_with("Defined in both the saved configuration and `"));
+                assert!(problems[0].message().ends_with("work.json`"));
             }
+            other => panic!("Expected duplicate names, got {other:?}"),
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_saved_targets_are_not_validated_again() {
+        let directory = drop_in_directory("unchecked");
+        // Saved before `suggestion` had to be used, which is now a problem
+        std::fs::write(
+            directory.join(CONFIG_FILE),
+            r#"[{"name": "old", "alias": "", "command": "https://old/?q=",
+                 "suggestion": "https://old/s?q=", "parser": "NONE"}]"#,
+        )
+        .unwrap();
+        drop_in(
+            &directory,
+            "work.json",
+            r#"[{"name": "jira", "alias": "", "command": "https://jira/?q=",
+                 "suggestion": "", "parser": "NONE"}]"#,
+        );
+
+        assert!(!load(directory.join(CONFIG_FILE))
+            .unwrap()
+            .validate()
+            .is_empty());
+        assert_eq!(load_in(&directory).unwrap().list_targets(), ["old", "jira"]);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_drop_in() {
+        let directory = drop_in_directory("invalid");
+        drop_in(&directory, "broken.yaml", "- name: [");
+        assert!(matches!(
+            load_in(&directory),
+            Err(error::Error::Include(path, _)) if path.ends_with("broken.yaml")
+        ));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_extends() {
+        let executors = from_text(
+            "- name: google\n  alias: g\n  command: https://google.com/?q=\n  \
+             suggestion: https://google.com/s?q=\n  parser: !JSON\n    path: /1\n\
+             - name: rust\n  alias: r\n  extends: google\n  prefix: 'site:docs.rs '\n",
+        )
+        .unwrap();
+        let rust = executors.find("rust").unwrap();
+        assert_eq!(rust.command(), "https://google.com/?q=");
+        assert_eq!(rust.suggestion, "https://google.com/s?q=");
+        assert_eq!(rust.parser, executors.find("google").unwrap().parser);
+        assert_eq!(
+            rust.url("async traits"),
+            "https://google.com/?q=site:docs.rs async traits"
+        );
+
+        let problems = problems(
+            r#"[{"name": "a", "alias": "", "extends": "b"},
+                {"name": "b", "alias": "", "extends": "a", "suffix": 3}]"#,
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].field(), "extends");
+        assert_eq!(problems[0].line(), Some(2));
+    }
+
+    #[test]
+    fn test_url() {
+        let executors = from_json(
+            r#"[{"name": "gh", "alias": "", "command": "https://github.com/search?q=",
+                 "suggestion": "", "parser": "NONE", "suffix": "&type=issues"},
+                {"name": "ghc", "alias": "", "extends": "gh", "suffix": "&type=code"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            executors.find("gh").unwrap().url("vai"),
+            "https://github.com/search?q=vai&type=issues"
+        );
+        assert_eq!(
+            executors.find("ghc").unwrap().url(""),
+            "https://github.com/search?q=&type=code"
+        );
+    }
+
+    #[test]
+    fn test_derived_targets_follow_their_base() {
+        let directory = crate::testing::directory("derived");
+        let config = directory.join(CONFIG_FILE);
+        let base = |command| {
+            format!(
+                "[[target]]\nname = \"google\"\nalias = \"g\"\ncommand = \"{command}\"\n\
+                 suggestion = \"\"\nparser = \"NONE\"\n\n\
+                 [[target]]\nname = \"Rust\"\nalias = \"\"\nextends = \"google\"\n\
+                 prefix = \"site:docs.rs \"\nhttp = {{ retries = 2 }}\n"
+            )
+        };
+
+        let executors = from_text(&base("https://google.com/?q=")).unwrap();
+        executors.save(&config).unwrap();
+        let saved = std::fs::read_to_string(&config).unwrap();
+        assert!(saved.contains(r#""extends": "google""#));
+        let loaded = load(&config).unwrap();
+        assert_eq!(loaded, executors);
+        for format in [Format::Json, Format::Toml, Format::Yaml] {
+            let exported = loaded.to_format(format).unwrap();
+            assert_eq!(from_format(&exported, format).unwrap(), executors);
         }
 
-        None
+        from_text(&base("https://google.de/?q="))
+            .unwrap()
+            .save(&config)
+            .unwrap();
+        assert_eq!(
+            load(&config).unwrap().find("rust").unwrap().command(),
+            "https://google.de/?q="
+        );
+
+        let orphan = Executors::new(
+            executors
+                .iter()
+                .filter(|executor| executor.name() == "rust")
+                .cloned()
+                .collect(),
+        );
+        assert!(matches!(
+            orphan.save(&config),
+            Err(error::Error::Invalid(_))
+        ));
+        assert_eq!(load(&config).unwrap().list_targets(), ["google", "rust"]);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_drop_in_extends() {
+        let directory = drop_in_directory("extends");
+        drop_in(
+            &directory,
+            "10-base.toml",
+            "[[target]]\nname = \"news\"\nextends = \"google\"\nsuffix = \" when:1d\"\n",
+        );
+        drop_in(
+            &directory,
+            "20-derived.json",
+            r#"[{"name": "hn", "extends": "news", "prefix": "site:ycombinator.com "}]"#,
+        );
+
+        let executors = load_in(&directory).unwrap();
+        let hn = executors.find("hn").unwrap();
+        assert_eq!(hn.command(), "https://google.com/?q=");
+        assert_eq!(
+            hn.url("rust"),
+            "https://google.com/?q=site:ycombinator.com rust when:1d"
+        );
+
+        drop_in(
+            &directory,
+            "30-broken.yaml",
+            "- name: broken\n  extends: nowhere\n",
+        );
+        assert!(matches!(
+            load_in(&directory),
+            Err(error::Error::Include(path, _)) if path.ends_with("30-broken.yaml")
+        ));
+        std::fs::remove_dir_all(&directory).unwrap();
     }
 }
@@ -0,0 +1,281 @@
+use super::suggestion::Suggestions;
+
+type Fetch = dyn Fn(&str) -> Option<Suggestions> + Send + Sync;
+
+struct State {
+    generation: u64,
+    requested: Option<String>,
+    /// The last query fetched, along with its suggestions, or `None` if fetching them failed
+    latest: Option<(String, Option<std::sync::Arc<Suggestions>>)>,
+}
+
+struct Shared {
+    state: std::sync::Mutex<State>,
+    ready: std::sync::Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Fetches [`Suggestions`](../suggestion/struct.Suggestions.html) in a background thread
+///
+/// Requests are debounced, so that only the last of a quick succession of queries is fetched.
+/// Each query is then fetched in a thread of its own, so a slow request never holds back newer
+/// ones. Whenever a new query is requested, any outdated request is abandoned: it is left to
+/// finish in the background and its results are discarded
+///
+/// The debouncing thread finishes once the `Fetcher` is dropped, and the fetching threads once
+/// their request completes
+pub struct Fetcher {
+    requests: std::sync::mpsc::Sender<(u64, String)>,
+    shared: std::sync::Arc<Shared>,
+}
+
+impl Fetcher {
+    pub(crate) fn spawn(fetch: Box<Fetch>, debounce: std::time::Duration) -> Self {
+        let (requests, receiver) = std::sync::mpsc::channel();
+        let shared = std::sync::Arc::new(Shared {
+            state: std::sync::Mutex::new(State {
+                generation: 0,
+                requested: None,
+                latest: None,
+            }),
+            ready: std::sync::Condvar::new(),
+        });
+
+        let worker_shared = shared.clone();
+        let fetch = std::sync::Arc::from(fetch);
+        std::thread::spawn(move || work(&receiver, &worker_shared, &fetch, debounce));
+
+        Self { requests, shared }
+    }
+
+    /// Requests suggestions for `query` without blocking
+    ///
+    /// Any previous request that has not yet completed is abandoned, so its results are never
+    /// returned
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query string to to get suggestions for
+    pub fn request(&self, query: &str) {
+        let mut state = self.shared.lock();
+        if state.requested.as_deref() == Some(query) {
+            return;
+        }
+
+        state.generation += 1;
+        state.requested = Some(String::from(query));
+        // The worker only stops when this instance is dropped
+        self.requests
+            .send((state.generation, String::from(query)))
+            .ok();
+    }
+
+    /// Returns the suggestions for `query`, if they were already fetched
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query string to to get suggestions for
+    #[must_use]
+    pub fn get(&self, query: &str) -> Option<std::sync::Arc<Suggestions>> {
+        Self::latest_for(&self.shared.lock(), query)
+    }
+
+    /// Requests suggestions for `query` and waits up to `timeout` for them to be fetched
+    ///
+    /// Returns as soon as fetching finishes, even if it failed
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query string to to get suggestions for
+    /// * `timeout` - Maximum time to wait for the suggestions
+    #[must_use]
+    pub fn wait(
+        &self,
+        query: &str,
+        timeout: std::time::Duration,
+    ) -> Option<std::sync::Arc<Suggestions>> {
+        self.request(query);
+
+        let deadline = std::time::Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some((_, suggestions)) =
+                state.latest.as_ref().filter(|(latest, _)| latest == query)
+            {
+                return suggestions.clone();
+            }
+
+            let remaining = deadline.checked_duration_since(std::time::Instant::now())?;
+            state = self
+                .shared
+                .ready
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    fn latest_for(state: &State, query: &str) -> Option<std::sync::Arc<Suggestions>> {
+        state
+            .latest
+            .as_ref()
+            .filter(|(latest, _)| latest == query)
+            .and_then(|(_, suggestions)| suggestions.clone())
+    }
+}
+
+fn work(
+    receiver: &std::sync::mpsc::Receiver<(u64, String)>,
+    shared: &std::sync::Arc<Shared>,
+    fetch: &std::sync::Arc<Fetch>,
+    debounce: std::time::Duration,
+) {
+    while let Ok(mut request) = receiver.recv() {
+        loop {
+            match receiver.recv_timeout(debounce) {
+                Ok(newer) => request = newer,
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => break,
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let (generation, query) = request;
+        let (shared, fetch) = (shared.clone(), fetch.clone());
+        std::thread::spawn(move || {
+            let suggestions = fetch(&query).map(std::sync::Arc::new);
+
+            let mut state = shared.lock();
+            if state.generation == generation {
+                state.latest = Some((query, suggestions));
+                shared.ready.notify_all();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suggestion::{Origin, Status, Suggestion};
+
+    fn fetcher(
+        delay: std::time::Duration,
+        debounce: std::time::Duration,
+    ) -> (Fetcher, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+        let queries = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = queries.clone();
+        let fetcher = Fetcher::spawn(
+            Box::new(move |query| {
+                log.lock().unwrap().push(String::from(query));
+                std::thread::sleep(delay);
+                Some(Suggestions::new(
+                    vec![Suggestion::new(format!("{query}!"), Origin::Remote)],
+                    Status::Fetched,
+                ))
+            }),
+            debounce,
+        );
+        (fetcher, queries)
+    }
+
+    #[test]
+    fn test_fetches_in_background() {
+        let (fetcher, _) = fetcher(
+            std::time::Duration::ZERO,
+            std::time::Duration::from_millis(10),
+        );
+        assert!(fetcher.get("rust").is_none());
+
+        let suggestions = fetcher
+            .wait("rust", std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(suggestions.suggestions()[0].text(), "rust!");
+        assert!(fetcher.get("rust").is_some());
+        assert!(fetcher.get("rus").is_none());
+    }
+
+    #[test]
+    fn test_debounces_requests() {
+        let (fetcher, queries) = fetcher(
+            std::time::Duration::ZERO,
+            std::time::Duration::from_millis(100),
+        );
+        fetcher.request("r");
+        fetcher.request("ru");
+        fetcher.request("rus");
+        assert!(fetcher
+            .wait("rust", std::time::Duration::from_secs(5))
+            .is_some());
+        assert_eq!(*queries.lock().unwrap(), ["rust"]);
+    }
+
+    #[test]
+    fn test_discards_outdated_results() {
+        let (fetcher, queries) = fetcher(
+            std::time::Duration::from_millis(200),
+            std::time::Duration::ZERO,
+        );
+        fetcher.request("old");
+        while queries.lock().unwrap().is_empty() {
+            std::thread::yield_now();
+        }
+        fetcher.request("new");
+
+        assert!(fetcher
+            .wait("new", std::time::Duration::from_secs(5))
+            .is_some());
+        assert!(fetcher.get("old").is_none());
+        assert_eq!(*queries.lock().unwrap(), ["old", "new"]);
+    }
+
+    #[test]
+    fn test_slow_requests_do_not_hold_back_newer_ones() {
+        let fetcher = Fetcher::spawn(
+            Box::new(|query| {
+                if query == "slow" {
+                    std::thread::sleep(std::time::Duration::from_secs(5));
+                }
+                Some(Suggestions::new(Vec::new(), Status::Fetched))
+            }),
+            std::time::Duration::ZERO,
+        );
+        fetcher.request("slow");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let start = std::time::Instant::now();
+        assert!(fetcher
+            .wait("fast", std::time::Duration::from_secs(2))
+            .is_some());
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert!(fetcher.get("slow").is_none());
+    }
+
+    #[test]
+    fn test_failures_end_the_wait() {
+        let fetcher = Fetcher::spawn(Box::new(|_| None), std::time::Duration::ZERO);
+        let start = std::time::Instant::now();
+        assert!(fetcher
+            .wait("rust", std::time::Duration::from_secs(5))
+            .is_none());
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert!(fetcher.get("rust").is_none());
+    }
+
+    #[test]
+    fn test_times_out() {
+        let (fetcher, _) = fetcher(
+            std::time::Duration::from_millis(500),
+            std::time::Duration::ZERO,
+        );
+        assert!(fetcher
+            .wait("rust", std::time::Duration::from_millis(10))
+            .is_none());
+    }
+}
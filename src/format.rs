@@ -0,0 +1,365 @@
+//! Text formats a configuration can be imported from and exported to
+//!
+//! JSON configurations are an array of targets. TOML configurations list them as an array of
+//! tables named `target`, and YAML configurations as a sequence
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::error;
+use super::executors::Executor;
+use super::inheritance;
+use super::validation::{self, Problem};
+use super::Result;
+
+/// A text format for configurations
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            _ => Err(format!("Unknown format: {input}")),
+        }
+    }
+}
+
+impl Format {
+    /// Guesses the format of `config` from its first line that is neither blank nor a comment
+    ///
+    /// JSON starts with `[` or `{`, TOML with a `[[target]]` header or a `key = value` pair and
+    /// anything else is taken as YAML
+    #[must_use]
+    pub fn detect(config: &str) -> Self {
+        let line = config
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or_default();
+
+        if let Some(rest) = line.strip_prefix('[') {
+            let rest = rest.trim_start();
+            if rest.starts_with('[') || rest.starts_with(char::is_alphabetic) {
+                Self::Toml
+            } else {
+                Self::Json
+            }
+        } else if line.is_empty() || line.starts_with('{') {
+            Self::Json
+        } else if line.starts_with('-') {
+            Self::Yaml
+        } else if line
+            .split_once('=')
+            .is_some_and(|(key, _)| !key.contains(':'))
+        {
+            Self::Toml
+        } else {
+            Self::Yaml
+        }
+    }
+}
+
+/// A target as written out: derived targets keep only the fields they declare
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Target<'a> {
+    Derived(&'a Value),
+    Complete(&'a Executor),
+}
+
+impl<'a> From<&'a Executor> for Target<'a> {
+    fn from(executor: &'a Executor) -> Self {
+        executor
+            .declared()
+            .map_or(Self::Complete(executor), Self::Derived)
+    }
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    target: Vec<Target<'a>>,
+}
+
+#[derive(Deserialize)]
+struct OwnedDocument {
+    #[serde(default)]
+    target: Vec<Executor>,
+}
+
+pub(crate) fn serialize<'a, I: Iterator<Item = &'a Executor>>(
+    executors: I,
+    format: Format,
+) -> Result<String> {
+    let executors = executors.map(Target::from).collect::<Vec<_>>();
+    match format {
+        Format::Json => serde_json::to_string_pretty(&executors).map_err(error::Serialize::from),
+        Format::Toml => {
+            toml::to_string_pretty(&Document { target: executors }).map_err(error::Serialize::from)
+        }
+        Format::Yaml => serde_yaml::to_string(&executors).map_err(error::Serialize::from),
+    }
+    .map_err(error::Error::Serialize)
+}
+
+/// Deserializes the targets in `config`, reporting a failure as a single
+/// [problem](../validation/struct.Problem.html)
+///
+/// Targets declaring `extends` are first resolved against the other targets in `config`, then
+/// against `bases`. Missing bases and inheritance cycles are all reported
+pub(crate) fn deserialize(
+    config: &str,
+    format: Format,
+    bases: &[Executor],
+) -> Result<Vec<Executor>> {
+    match values(config, format) {
+        Some(values) if inheritance::uses_extends(&values) => {
+            deserialize_extended(config, format, &values, bases)
+        }
+        _ => deserialize_plain(config, format),
+    }
+}
+
+fn deserialize_plain(config: &str, format: Format) -> Result<Vec<Executor>> {
+    match format {
+        Format::Json => {
+            serde_json::from_str(config).map_err(|e| validation::deserialization(config, &e))
+        }
+        Format::Toml => toml::from_str::<OwnedDocument>(config)
+            .map(|document| document.target)
+            .map_err(|e| {
+                let position = e.span().map(|span| position(config, span.start));
+                let field = position
+                    .and_then(|(line, _)| config.lines().nth(line - 1))
+                    .and_then(|line| line.split_once('='))
+                    .map_or("", |(key, _)| key.trim());
+                validation::deserialization_at(None, field, e.message(), position)
+            }),
+        Format::Yaml => serde_yaml::from_str(config).map_err(|e| {
+            let position = e
+                .location()
+                .map(|location| (location.line(), location.column()));
+            let message = e.to_string();
+            let (index, field, message) = yaml_path(&message);
+            validation::deserialization_at(index.map(|index| (index, "")), field, message, position)
+        }),
+    }
+    .map_err(|problem: Problem| error::Error::Invalid(vec![problem]))
+}
+
+fn deserialize_extended(
+    config: &str,
+    format: Format,
+    values: &[Value],
+    bases: &[Executor],
+) -> Result<Vec<Executor>> {
+    from_values(values, bases).map_err(|problems| {
+        error::Error::Invalid(if format == Format::Json {
+            validation::locate(config, problems)
+        } else {
+            problems
+        })
+    })
+}
+
+/// Resolves and deserializes the targets in `values`, keeping the fields declared by the derived
+/// ones so they can be written out as they were
+pub(crate) fn from_values(
+    values: &[Value],
+    bases: &[Executor],
+) -> std::result::Result<Vec<Executor>, Vec<Problem>> {
+    inheritance::resolve(values, bases)?
+        .into_iter()
+        .zip(values)
+        .enumerate()
+        .map(|(index, (value, declared))| {
+            let name = value
+                .get("name")
+                .and_then(Value::as_str)
+                .map(String::from)
+                .unwrap_or_default();
+            let executor = serde_json::from_value::<Executor>(value).map_err(|e| {
+                let message = e.to_string();
+                let field = message
+                    .strip_prefix("missing field `")
+                    .and_then(|field| field.strip_suffix('`'))
+                    .unwrap_or_default();
+                vec![validation::deserialization_at(
+                    Some((index, &name)),
+                    field,
+                    &message,
+                    None,
+                )]
+            })?;
+            Ok(match declared {
+                Value::Object(fields) if fields.get("extends").is_some_and(Value::is_string) => {
+                    executor.declaring(Value::Object(without_nulls(fields)))
+                }
+                _ => executor,
+            })
+        })
+        .collect()
+}
+
+/// Drops the fields set to `null`, which TOML cannot write and which mean the default anyway
+fn without_nulls(fields: &serde_json::Map<String, Value>) -> serde_json::Map<String, Value> {
+    fields
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect()
+}
+
+/// Reads the targets in `config` as plain values, or `None` if they are not even an array of
+/// values, leaving the error to the typed deserialization
+fn values(config: &str, format: Format) -> Option<Vec<Value>> {
+    match format {
+        Format::Json => serde_json::from_str(config).ok(),
+        Format::Toml => match toml::from_str::<toml::Table>(config).ok()?.remove("target") {
+            None => Some(Vec::new()),
+            Some(toml::Value::Array(targets)) => targets
+                .into_iter()
+                .map(|target| serde_json::to_value(target).ok())
+                .collect(),
+            Some(_) => None,
+        },
+        Format::Yaml => serde_yaml::from_str::<Vec<serde_yaml::Value>>(config)
+            .ok()?
+            .into_iter()
+            .map(from_yaml)
+            .collect(),
+    }
+}
+
+/// Converts a YAML value into JSON, turning tags such as `!JSONP` into single-key objects
+fn from_yaml(value: serde_yaml::Value) -> Option<Value> {
+    use serde_yaml::Value as Yaml;
+
+    match value {
+        Yaml::Tagged(tagged) => {
+            let tagged = *tagged;
+            let tag = tagged.tag.to_string();
+            let mut object = serde_json::Map::new();
+            object.insert(
+                String::from(tag.trim_start_matches('!')),
+                from_yaml(tagged.value)?,
+            );
+            Some(Value::Object(object))
+        }
+        Yaml::Sequence(values) => values
+            .into_iter()
+            .map(from_yaml)
+            .collect::<Option<_>>()
+            .map(Value::Array),
+        Yaml::Mapping(mapping) => mapping
+            .into_iter()
+            .map(|(key, value)| Some((String::from(key.as_str()?), from_yaml(value)?)))
+            .collect::<Option<_>>()
+            .map(Value::Object),
+        value => serde_json::to_value(value).ok(),
+    }
+}
+
+/// Converts a byte offset into a line and column, both starting at 1
+fn position(config: &str, offset: usize) -> (usize, usize) {
+    let before = &config[..offset.min(config.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Splits the path YAML errors start with, such as `.[0].parser: `, into the index of the target
+/// and the field
+fn yaml_path(message: &str) -> (Option<usize>, &str, &str) {
+    let Some((path, rest)) = message
+        .strip_prefix(".[")
+        .and_then(|path| path.split_once(": "))
+    else {
+        return (None, "", message);
+    };
+    let Some((index, field)) = path.split_once(']') else {
+        return (None, "", message);
+    };
+    match index.parse() {
+        Ok(index) => (Some(index), field.trim_start_matches('.'), rest),
+        Err(_) => (None, "", message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect("[\n  {\"name\": \"a\"}\n]"), Format::Json);
+        assert_eq!(Format::detect("  [{\"name\": \"a\"}]"), Format::Json);
+        assert_eq!(Format::detect("[]"), Format::Json);
+        assert_eq!(Format::detect(""), Format::Json);
+        assert_eq!(
+            Format::detect("# Mine\n\n[[target]]\nname = \"a\""),
+            Format::Toml
+        );
+        assert_eq!(Format::detect("[[ target ]]"), Format::Toml);
+        assert_eq!(Format::detect("target = []"), Format::Toml);
+        assert_eq!(Format::detect("---\n- name: a"), Format::Yaml);
+        assert_eq!(Format::detect("# Mine\n- name: a"), Format::Yaml);
+        assert_eq!(Format::detect("[] # empty"), Format::Json);
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("TOML".parse(), Ok(Format::Toml));
+        assert_eq!("yml".parse(), Ok(Format::Yaml));
+        assert!("xml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let executors = crate::executors::from_json(include_str!("../config.json")).unwrap();
+        for format in [Format::Json, Format::Toml, Format::Yaml] {
+            let config = serialize(executors.iter(), format).unwrap();
+            assert_eq!(Format::detect(&config), format);
+            assert_eq!(
+                deserialize(&config, format, &[]).unwrap(),
+                executors.iter().cloned().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    fn problem(config: &str, format: Format) -> Problem {
+        match deserialize(config, format, &[]) {
+            Err(error::Error::Invalid(mut problems)) if problems.len() == 1 => problems.remove(0),
+            other => panic!("Expected a single problem, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_toml_problems() {
+        let problem = problem("[[target]]\nname = 3\n", Format::Toml);
+        assert_eq!(problem.field(), "name");
+        assert_eq!(problem.line(), Some(2));
+        assert_eq!(problem.column(), Some(8));
+        assert!(problem.message().starts_with("Invalid type: integer `3`"));
+    }
+
+    #[test]
+    fn test_yaml_problems() {
+        let problem = problem("- name: a\n  parser: {}\n", Format::Yaml);
+        assert_eq!(problem.target(), Some("#1"));
+        assert_eq!(problem.field(), "parser");
+        assert_eq!(problem.line(), Some(2));
+        assert!(!problem.message().contains("at line"));
+    }
+}
@@ -0,0 +1,661 @@
+use serde::{Deserialize, Serialize};
+
+use super::error;
+use super::validation;
+use super::Result;
+
+const RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(30);
+const BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+const DEFAULT_CONNECT_TIMEOUT: u64 = 2000;
+const DEFAULT_READ_TIMEOUT: u64 = 3000;
+const DEFAULT_RETRIES: u32 = 1;
+const MAX_RETRIES: u32 = 5;
+const DEFAULT_USER_AGENT: &str = concat!("vai/", env!("CARGO_PKG_VERSION"));
+const BODY_LIMIT: u64 = 10 * 1024 * 1024;
+
+static FORCED_OFFLINE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+static FAILURES: std::sync::OnceLock<
+    std::sync::Mutex<std::collections::HashMap<String, std::time::Instant>>,
+> = std::sync::OnceLock::new();
+static AGENTS: std::sync::OnceLock<
+    std::sync::Mutex<std::collections::HashMap<Client, ureq::Agent>>,
+> = std::sync::OnceLock::new();
+
+/// HTTP settings used when fetching suggestions
+///
+/// Each target may override any of the settings. Whatever is not set falls back to the global
+/// settings, given by the environment, and then to the defaults
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Default, Clone, PartialEq, Eq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// Milliseconds to wait for a connection. Defaults to 2000
+    connect_timeout: Option<u64>,
+    /// Milliseconds to wait for the response. Defaults to 3000
+    read_timeout: Option<u64>,
+    /// HTTP or SOCKS proxy URL, such as `socks5://localhost:1080`
+    proxy: Option<String>,
+    /// `User-Agent` header to send. Defaults to `vai/<version>`
+    user_agent: Option<String>,
+    /// How many times refused connections and server errors are retried, up to 5. Defaults to 1
+    retries: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Client {
+    connect_timeout: u64,
+    read_timeout: u64,
+    proxy: Option<String>,
+    user_agent: String,
+}
+
+fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+}
+
+impl Settings {
+    /// Reads the global settings for requests to `url` from the environment
+    fn from_env(url: &str) -> Self {
+        Self {
+            connect_timeout: env("VAI_CONNECT_TIMEOUT"),
+            read_timeout: env("VAI_READ_TIMEOUT"),
+            proxy: proxy_for(url, env),
+            user_agent: env("VAI_USER_AGENT"),
+            retries: env("VAI_RETRIES"),
+        }
+    }
+
+    fn or(&self, fallback: Self) -> Self {
+        Self {
+            connect_timeout: self.connect_timeout.or(fallback.connect_timeout),
+            read_timeout: self.read_timeout.or(fallback.read_timeout),
+            proxy: self.proxy.clone().or(fallback.proxy),
+            user_agent: self.user_agent.clone().or(fallback.user_agent),
+            retries: self.retries.or(fallback.retries),
+        }
+    }
+
+    fn client(&self) -> Client {
+        Client {
+            connect_timeout: self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: self.read_timeout.unwrap_or(DEFAULT_READ_TIMEOUT),
+            proxy: self.proxy.clone(),
+            user_agent: self
+                .user_agent
+                .clone()
+                .unwrap_or_else(|| String::from(DEFAULT_USER_AGENT)),
+        }
+    }
+
+    /// Returns the problems with these settings, such as an unusable proxy
+    pub(crate) fn problems(&self) -> Vec<validation::Problem> {
+        let mut problems = Vec::new();
+        if let Some(Err(error)) = self.proxy.as_deref().map(ureq::Proxy::new) {
+            problems.push(validation::Problem::new(
+                "proxy",
+                format!("Invalid proxy: {error}"),
+                Some(String::from(
+                    "use a proxy URL, such as `socks5://localhost:1080`",
+                )),
+            ));
+        }
+        if self.retries.is_some_and(|retries| retries > MAX_RETRIES) {
+            problems.push(validation::Problem::new(
+                "retries",
+                format!("At most {MAX_RETRIES} retries are attempted"),
+                Some(format!("set `retries` to {MAX_RETRIES} or less")),
+            ));
+        }
+        problems
+    }
+
+    fn retries(&self) -> u32 {
+        self.retries.unwrap_or(DEFAULT_RETRIES).min(MAX_RETRIES)
+    }
+}
+
+/// Returns the proxy that the variables given by `var` set for `url`, if any
+///
+/// That is `VAI_PROXY`, or else the proxy for the scheme of `url` followed by `ALL_PROXY`, unless
+/// the host of `url` is listed in `NO_PROXY`
+fn proxy_for<F: Fn(&str) -> Option<String>>(url: &str, var: F) -> Option<String> {
+    let scheme = match url.split_once("://") {
+        Some((scheme, _)) if scheme.eq_ignore_ascii_case("http") => ["HTTP_PROXY", "http_proxy"],
+        _ => ["HTTPS_PROXY", "https_proxy"],
+    };
+    let proxy = std::iter::once("VAI_PROXY")
+        .chain(scheme)
+        .chain(["ALL_PROXY", "all_proxy"])
+        .find_map(|name| var(name).filter(|proxy| !proxy.is_empty()))?;
+
+    let no_proxy = ["NO_PROXY", "no_proxy"]
+        .into_iter()
+        .find_map(&var)
+        .unwrap_or_default();
+    (!bypasses(&no_proxy, url)).then_some(proxy)
+}
+
+/// Returns `true` if `no_proxy`, a comma separated list of hosts such as `localhost,.corp.com`,
+/// covers the host of `url`
+///
+/// Domains also cover their subdomains, entries with a port only cover that port and `*` covers
+/// every host
+fn bypasses(no_proxy: &str, url: &str) -> bool {
+    let authority = host(url);
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, authority)| authority);
+    let (hostname, port) = split_port(authority);
+    let hostname = hostname.to_ascii_lowercase();
+
+    no_proxy
+        .split([',', ' '])
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            let (entry, entry_port) = split_port(entry);
+            if entry_port.is_some_and(|entry_port| Some(entry_port) != port) {
+                return false;
+            }
+            let domain = entry
+                .trim_start_matches('*')
+                .trim_start_matches('.')
+                .to_ascii_lowercase();
+            hostname == domain || hostname.ends_with(&format!(".{domain}"))
+        })
+}
+
+/// Splits `authority` into its host and its port, if any, leaving IPv6 addresses whole
+fn split_port(authority: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = authority.strip_prefix('[') {
+        return match rest.split_once(']') {
+            Some((address, port)) => (address, port.strip_prefix(':')),
+            None => (authority, None),
+        };
+    }
+    match authority.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host, Some(port)),
+        _ => (authority, None),
+    }
+}
+
+impl Client {
+    fn build(&self) -> Result<ureq::Agent> {
+        let builder = ureq::AgentBuilder::new()
+            .timeout_connect(std::time::Duration::from_millis(self.connect_timeout))
+            .timeout_read(std::time::Duration::from_millis(self.read_timeout))
+            .user_agent(&self.user_agent);
+
+        Ok(match &self.proxy {
+            Some(proxy) => builder.proxy(ureq::Proxy::new(proxy)?),
+            None => builder,
+        }
+        .build())
+    }
+}
+
+/// Returns the agent for `client`, creating it if this is the first time it is needed
+///
+/// Targets without specific settings all share the same agent
+fn agent(client: &Client) -> Result<ureq::Agent> {
+    let mut agents = AGENTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    if let Some(agent) = agents.get(client) {
+        return Ok(agent.clone());
+    }
+
+    let agent = client.build()?;
+    agents.insert(client.clone(), agent.clone());
+    Ok(agent)
+}
+
+/// Performs a GET on `url` using the `target` settings on top of the global ones
+///
+/// Refused connections, server errors and rate limiting are retried a bounded number of times.
+/// Timeouts are not, since every attempt would wait as long again
+pub(crate) fn get(url: &str, target: &Settings) -> Result<ureq::Response> {
+    send(url, &target.or(Settings::from_env(url)))
+}
+
+fn send(url: &str, settings: &Settings) -> Result<ureq::Response> {
+    let agent = agent(&settings.client())?;
+    let retries = settings.retries();
+
+    let mut attempt = 0;
+    loop {
+        let response = agent.get(url).call();
+        let retriable = match &response {
+            Ok(_) => false,
+            Err(ureq::Error::Transport(transport)) => failed_to_connect(transport),
+            Err(ureq::Error::Status(code, _)) => *code == 429 || *code >= 500,
+        };
+
+        if !retriable || attempt >= retries {
+            report(url, &response);
+            return response.map_err(error::Error::from);
+        }
+
+        std::thread::sleep(BACKOFF * 2_u32.pow(attempt));
+        attempt += 1;
+    }
+}
+
+/// Returns `true` if `transport` failed to connect before the connect timeout, such as when the
+/// connection was refused
+fn failed_to_connect(transport: &ureq::Transport) -> bool {
+    use std::error::Error;
+
+    transport.kind() == ureq::ErrorKind::ConnectionFailed
+        && transport
+            .source()
+            .and_then(|source| source.downcast_ref::<std::io::Error>())
+            .is_none_or(|error| error.kind() != std::io::ErrorKind::TimedOut)
+}
+
+/// Reads the body of `response` as text
+///
+/// The body is decoded from `charset`, if given, or from the charset declared in the
+/// `Content-Type` header, defaulting to UTF-8
+pub(crate) fn body(response: ureq::Response, charset: Option<&str>) -> Result<String> {
+    use std::io::Read;
+
+    let encoding = encoding(charset, response.charset())?;
+
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(BODY_LIMIT)
+        .read_to_end(&mut bytes)
+        .map_err(error::Parse::from)
+        .map_err(error::Error::Parse)?;
+    Ok(decode(&bytes, encoding))
+}
+
+/// Decodes `bytes` with the `charset` override, if any, or as UTF-8
+pub(crate) fn decode_as(bytes: &[u8], charset: Option<&str>) -> Result<String> {
+    Ok(decode(bytes, encoding(charset, "utf-8")?))
+}
+
+fn encoding(charset: Option<&str>, declared: &str) -> Result<&'static encoding_rs::Encoding> {
+    match charset {
+        Some(charset) => encoding_rs::Encoding::for_label(charset.trim().as_bytes())
+            .ok_or_else(|| error::Error::Parse(error::Parse::Charset(String::from(charset)))),
+        None => {
+            Ok(encoding_rs::Encoding::for_label(declared.as_bytes()).unwrap_or(encoding_rs::UTF_8))
+        }
+    }
+}
+
+fn decode(bytes: &[u8], encoding: &'static encoding_rs::Encoding) -> String {
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// Forces offline mode for the rest of the process
+///
+/// While offline, no suggestion API is queried and only cached and historic suggestions are used.
+/// Offline mode can also be enabled by setting the `VAI_OFFLINE` environment variable
+pub fn set_offline(offline: bool) {
+    FORCED_OFFLINE.store(offline, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Returns `true` if the network should not be used at all
+///
+/// This is the case if offline mode was [forced](fn.set_offline.html) or if `VAI_OFFLINE` is set
+#[must_use]
+pub fn is_offline() -> bool {
+    FORCED_OFFLINE.load(std::sync::atomic::Ordering::Relaxed)
+        || std::env::var("VAI_OFFLINE")
+            .is_ok_and(|value| !matches!(value.as_str(), "" | "0" | "false"))
+}
+
+/// Returns `true` if the network should not be used to reach `url`
+///
+/// Besides [offline mode](fn.is_offline.html), this is the case if a request failed to reach the
+/// host of `url` in the last 30 seconds, in this process or in another one. Other hosts are still
+/// queried
+#[must_use]
+pub fn is_offline_for(url: &str) -> bool {
+    is_offline()
+        || FAILURES
+            .get()
+            .and_then(|failures| failures.lock().ok())
+            .and_then(|failures| failures.get(host(url)).copied())
+            .is_some_and(|instant| instant.elapsed() < RETRY_AFTER)
+        || super::cache::unreachable_since(host(url)).is_some_and(|since| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .is_ok_and(|now| now.as_secs().saturating_sub(since) < RETRY_AFTER.as_secs())
+        })
+}
+
+/// Returns the part of `url` between the scheme and the path, which failures are tracked by
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+/// Records the outcome of a request to `url`, so that unreachable hosts are detected automatically
+///
+/// The outcome is also kept in the cache directory, since every completion from the shell runs in
+/// a process of its own
+fn report(url: &str, result: &std::result::Result<ureq::Response, ureq::Error>) {
+    let unreachable = matches!(result, Err(ureq::Error::Transport(_)));
+    // Failing to share the outcome only means other processes try the host again
+    super::cache::set_unreachable(host(url), unreachable).ok();
+
+    let Ok(mut failures) = FAILURES.get_or_init(Default::default).lock() else {
+        return;
+    };
+    if unreachable {
+        failures.insert(String::from(host(url)), std::time::Instant::now());
+    } else {
+        failures.remove(host(url));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serve(responses: Vec<Vec<u8>>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = std::io::BufReader::new(stream);
+                    let mut request = String::new();
+                    while reader.read_line(&mut request).unwrap() > 2 {}
+                    if let Some(delay) = response.strip_prefix(b"SLEEP ") {
+                        std::thread::sleep(std::time::Duration::from_millis(
+                            std::str::from_utf8(delay).unwrap().parse().unwrap(),
+                        ));
+                    } else {
+                        reader.get_mut().write_all(&response).unwrap();
+                    }
+                    request
+                })
+                .collect()
+        });
+        (url, handle)
+    }
+
+    fn response(status: &str, body: &str) -> Vec<u8> {
+        response_with(status, "", body.as_bytes())
+    }
+
+    /// Builds a response with the extra `headers`, each ending with `\r\n`, and the raw `body`
+    fn response_with(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            connect_timeout: Some(1000),
+            read_timeout: Some(1000),
+            proxy: None,
+            user_agent: Some(String::from("vai-test")),
+            retries: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_user_agent() {
+        let (url, server) = serve(vec![response("200 OK", "ok")]);
+        let body = send(&url, &settings()).unwrap().into_string().unwrap();
+        assert_eq!(body, "ok");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].to_lowercase().contains("user-agent: vai-test"));
+    }
+
+    #[test]
+    fn test_retries() {
+        let (url, server) = serve(vec![
+            response("503 Service Unavailable", ""),
+            response("200 OK", "ok"),
+        ]);
+        let settings = Settings {
+            retries: Some(1),
+            ..settings()
+        };
+        let body = send(&url, &settings).unwrap().into_string().unwrap();
+        assert_eq!(body, "ok");
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_no_retry_on_client_error() {
+        let (url, server) = serve(vec![response("404 Not Found", "")]);
+        let settings = Settings {
+            retries: Some(3),
+            ..settings()
+        };
+        assert!(matches!(send(&url, &settings), Err(error::Error::Fetch(_))));
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        let (url, server) = serve(vec![b"SLEEP 500".to_vec()]);
+        let settings = Settings {
+            read_timeout: Some(100),
+            retries: Some(2),
+            ..settings()
+        };
+        let start = std::time::Instant::now();
+        assert!(matches!(send(&url, &settings), Err(error::Error::Fetch(_))));
+        // Retrying would take at least 100 + 100 + 100 + 200 + 100 milliseconds
+        assert!(start.elapsed() < std::time::Duration::from_millis(400));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_proxy_for() {
+        let proxy = |url, vars: &[(&str, &str)]| {
+            let vars = vars
+                .iter()
+                .map(|(name, value)| (String::from(*name), String::from(*value)))
+                .collect::<std::collections::HashMap<_, _>>();
+            proxy_for(url, |name| vars.get(name).cloned())
+        };
+        let vars = [
+            ("http_proxy", "http://plain:3128"),
+            ("HTTPS_PROXY", "http://secure:3128"),
+            ("NO_PROXY", "localhost,.corp.com, intranet:8080,[::1]"),
+        ];
+
+        assert_eq!(
+            proxy("http://example.com/", &vars).as_deref(),
+            Some("http://plain:3128")
+        );
+        assert_eq!(
+            proxy("HTTPS://example.com/", &vars).as_deref(),
+            Some("http://secure:3128")
+        );
+        assert_eq!(
+            proxy(
+                "https://example.com/",
+                &[("ALL_PROXY", "socks5://all:1080")]
+            )
+            .as_deref(),
+            Some("socks5://all:1080")
+        );
+        assert_eq!(
+            proxy(
+                "https://example.com/",
+                &[("VAI_PROXY", "socks5://vai:1080")]
+            )
+            .as_deref(),
+            Some("socks5://vai:1080")
+        );
+        assert!(proxy(
+            "http://example.com/",
+            &[("HTTPS_PROXY", "http://secure:3128")]
+        )
+        .is_none());
+
+        for bypassed in [
+            "http://localhost:8000/",
+            "https://user@LOCALHOST/",
+            "https://corp.com/",
+            "https://wiki.corp.com/?q=",
+            "http://intranet:8080/",
+            "http://[::1]:8000/",
+        ] {
+            assert!(proxy(bypassed, &vars).is_none(), "{bypassed}");
+        }
+        for proxied in [
+            "https://notcorp.com/",
+            "http://intranet:9090/",
+            "http://localhost.example.com/",
+        ] {
+            assert!(proxy(proxied, &vars).is_some(), "{proxied}");
+        }
+        assert!(proxy(
+            "https://example.com/",
+            &[("HTTPS_PROXY", "http://secure:3128"), ("no_proxy", "*")]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        let bytes = b"[\"bla\",[\"black\",\"blaafarvev\xe6rket\",\"blazer\"]]";
+        let encoding = encoding_rs::Encoding::for_label(b"ISO-8859-1").unwrap();
+        let decoded = decode(bytes, encoding);
+        let suggestions = crate::parser::parse(
+            &crate::parser::Parser::Google,
+            &crate::parser::Registry::default(),
+            &decoded,
+        )
+        .unwrap();
+        assert_eq!(suggestions[1].text(), "blaafarveværket");
+    }
+
+    #[test]
+    fn test_decode_other_charsets() {
+        let cyrillic = encoding_rs::Encoding::for_label(b"windows-1251").unwrap();
+        assert_eq!(decode(b"\xef\xf0\xe8\xe2\xe5\xf2", cyrillic), "привет");
+
+        let japanese = encoding_rs::Encoding::for_label(b"Shift_JIS").unwrap();
+        assert_eq!(decode(b"\x93\xfa\x96\x7b", japanese), "日本");
+    }
+
+    #[test]
+    fn test_body_uses_content_type() {
+        let (url, server) = serve(vec![response_with(
+            "200 OK",
+            "Content-Type: application/json; charset=ISO-8859-1\r\n",
+            b"[\"v\xe6r\"]",
+        )]);
+        let response = send(&url, &settings()).unwrap();
+        assert_eq!(body(response, None).unwrap(), "[\"vær\"]");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_body_override() {
+        let (url, server) = serve(vec![response("200 OK", "[\"vær\"]")]);
+        let utf8 = send(&url, &settings()).unwrap();
+        assert_eq!(body(utf8, Some("utf-8")).unwrap(), "[\"vær\"]");
+        server.join().unwrap();
+
+        let (url, server) = serve(vec![response("200 OK", "")]);
+        let unknown = send(&url, &settings()).unwrap();
+        assert!(matches!(
+            body(unknown, Some("klingon")),
+            Err(error::Error::Parse(error::Parse::Charset(_)))
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_failures_per_host() {
+        assert_eq!(
+            host("https://user@example.com:8080/path?q=a"),
+            "user@example.com:8080"
+        );
+        assert_eq!(host("example.com?q=a"), "example.com");
+
+        // Nothing listens on the port once the listener is dropped
+        let unreachable = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/?q=", listener.local_addr().unwrap())
+        };
+        assert!(matches!(
+            send(&unreachable, &settings()),
+            Err(error::Error::Fetch(_))
+        ));
+        assert!(is_offline_for(&format!("{unreachable}rust")));
+        // As seen by another process
+        FAILURES
+            .get()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .remove(host(&unreachable));
+        assert!(is_offline_for(&unreachable));
+        crate::cache::set_unreachable(host(&unreachable), false).unwrap();
+        assert!(!is_offline_for(&unreachable));
+
+        let (url, server) = serve(vec![response("200 OK", "ok")]);
+        assert!(!is_offline_for(&url));
+        assert!(send(&url, &settings()).is_ok());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_invalid_proxy() {
+        let settings = Settings {
+            proxy: Some(String::from("ftp://localhost")),
+            ..settings()
+        };
+        assert!(matches!(
+            send("http://localhost/", &settings),
+            Err(error::Error::Fetch(_))
+        ));
+    }
+
+    #[test]
+    fn test_target_overrides_global() {
+        let target = Settings {
+            user_agent: Some(String::from("target")),
+            ..Settings::default()
+        };
+        let global = Settings {
+            user_agent: Some(String::from("global")),
+            retries: Some(2),
+            ..Settings::default()
+        };
+        let merged = target.or(global);
+        assert_eq!(merged.user_agent.as_deref(), Some("target"));
+        assert_eq!(merged.retries, Some(2));
+        assert_eq!(merged.client().connect_timeout, DEFAULT_CONNECT_TIMEOUT);
+    }
+
+    #[test]
+    fn test_bounded_retries() {
+        let settings = Settings {
+            retries: Some(100),
+            ..Settings::default()
+        };
+        assert_eq!(settings.retries(), MAX_RETRIES);
+    }
+}
@@ -0,0 +1,350 @@
+//! Importing a configuration on top of the current one
+
+use super::executors::{Executor, Executors};
+
+/// How an imported configuration is combined with the current one
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Mode {
+    /// The imported configuration replaces the current one entirely
+    #[default]
+    Replace,
+    /// Imported targets are added, replacing current targets with the same name
+    Merge,
+    /// Imported targets are added, unless a current target has the same name
+    AddOnly,
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.to_lowercase().replace('-', "_").as_str() {
+            "replace" => Ok(Self::Replace),
+            "merge" => Ok(Self::Merge),
+            "add" | "add_only" => Ok(Self::AddOnly),
+            _ => Err(format!("Unknown import mode: {input}")),
+        }
+    }
+}
+
+/// Which of two targets with the same name is kept when importing
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Resolution {
+    /// Keep the current target
+    Current,
+    /// Replace it with the imported target
+    Incoming,
+}
+
+/// Names of the targets affected by an import
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Summary {
+    added: Vec<String>,
+    changed: Vec<String>,
+    kept: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl Summary {
+    /// Returns the targets that did not exist before
+    #[must_use]
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+
+    /// Returns the targets replaced by an imported target with the same name
+    #[must_use]
+    pub fn changed(&self) -> &[String] {
+        &self.changed
+    }
+
+    /// Returns the targets that differ from the imported ones, but were kept as they were
+    #[must_use]
+    pub fn kept(&self) -> &[String] {
+        &self.kept
+    }
+
+    /// Returns the targets that no longer exist
+    #[must_use]
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Returns `true` if the import changes nothing
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() && self.kept.is_empty() {
+            return write!(fmt, "No changes");
+        }
+
+        let mut separator = "";
+        for (label, names) in [
+            ("Added", &self.added),
+            ("Changed", &self.changed),
+            ("Kept", &self.kept),
+            ("Removed", &self.removed),
+        ] {
+            if !names.is_empty() {
+                write!(fmt, "{separator}{label}: {}", names.join(", "))?;
+                separator = "\n";
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of an import: the combined configuration and what changed
+#[derive(Debug)]
+pub struct Import {
+    executors: Executors,
+    summary: Summary,
+}
+
+impl Import {
+    /// Returns the combined configuration
+    #[must_use]
+    pub fn executors(&self) -> &Executors {
+        &self.executors
+    }
+
+    /// Returns what changed compared to the current configuration
+    #[must_use]
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+
+    /// Consumes this instance, returning the combined configuration
+    #[must_use]
+    pub fn into_executors(self) -> Executors {
+        self.executors
+    }
+}
+
+fn names<'a, I: Iterator<Item = &'a Executor>>(executors: I) -> Vec<String> {
+    executors
+        .map(|executor| String::from(executor.name()))
+        .collect()
+}
+
+/// Combines `incoming` with the `current` configuration according to `mode`
+///
+/// # Arguments
+///
+/// * `current` - The configuration in use
+/// * `incoming` - The configuration being imported
+/// * `mode` - How to combine them
+///
+/// # See also
+/// [`import_with(current, incoming, resolve)`](fn.import_with.html)
+#[must_use]
+pub fn import(current: &Executors, incoming: Executors, mode: Mode) -> Import {
+    match mode {
+        Mode::Replace => {
+            let exists = |executors: &Executors, name: &str| {
+                executors.iter().any(|executor| executor.name() == name)
+            };
+            let summary = Summary {
+                added: names(
+                    incoming
+                        .iter()
+                        .filter(|imported| !exists(current, imported.name())),
+                ),
+                changed: names(incoming.iter().filter(|imported| {
+                    current
+                        .iter()
+                        .any(|executor| executor.name() == imported.name() && executor != *imported)
+                })),
+                kept: vec![],
+                removed: names(
+                    current
+                        .iter()
+                        .filter(|executor| !exists(&incoming, executor.name())),
+                ),
+            };
+            Import {
+                executors: incoming,
+                summary,
+            }
+        }
+        Mode::Merge => import_with(current, incoming, |_, _| Resolution::Incoming),
+        Mode::AddOnly => import_with(current, incoming, |_, _| Resolution::Current),
+    }
+}
+
+/// Adds `incoming` to the `current` configuration, calling `resolve` for every imported target
+/// that differs from a current target with the same name
+///
+/// Current targets keep their order and new targets are added after them
+///
+/// # Arguments
+///
+/// * `current` - The configuration in use
+/// * `incoming` - The configuration being imported
+/// * `resolve` - Given the current and the imported target, decides which one is kept
+pub fn import_with<F>(current: &Executors, incoming: Executors, mut resolve: F) -> Import
+where
+    F: FnMut(&Executor, &Executor) -> Resolution,
+{
+    let mut summary = Summary::default();
+    let mut executors = current.iter().cloned().collect::<Vec<_>>();
+
+    for imported in incoming.into_vec() {
+        match executors
+            .iter_mut()
+            .find(|executor| executor.name() == imported.name())
+        {
+            Some(executor) if *executor == imported => {}
+            Some(executor) => match resolve(executor, &imported) {
+                Resolution::Incoming => {
+                    summary.changed.push(String::from(imported.name()));
+                    *executor = imported;
+                }
+                Resolution::Current => summary.kept.push(String::from(imported.name())),
+            },
+            None => {
+                summary.added.push(String::from(imported.name()));
+                executors.push(imported);
+            }
+        }
+    }
+
+    Import {
+        executors: Executors::new(executors),
+        summary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::executors;
+
+    fn commands(import: &Import) -> Vec<String> {
+        import
+            .executors()
+            .iter()
+            .map(|executor| format!("{}={}", executor.name(), executor.command()))
+            .collect()
+    }
+
+    fn current() -> Executors {
+        executors(&[
+            ("google", "google.com"),
+            ("duck", "duck.com"),
+            ("wiki", "w.org"),
+        ])
+    }
+
+    fn incoming() -> Executors {
+        executors(&[
+            ("wiki", "wikipedia.org"),
+            ("google", "google.com"),
+            ("rust", "docs.rs"),
+        ])
+    }
+
+    #[test]
+    fn test_replace() {
+        let import = import(&current(), incoming(), Mode::Replace);
+        assert_eq!(
+            commands(&import),
+            [
+                "wiki=https://wikipedia.org/?q=",
+                "google=https://google.com/?q=",
+                "rust=https://docs.rs/?q="
+            ]
+        );
+        assert_eq!(import.summary().added(), ["rust"]);
+        assert_eq!(import.summary().changed(), ["wiki"]);
+        assert_eq!(import.summary().removed(), ["duck"]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let import = import(&current(), incoming(), Mode::Merge);
+        assert_eq!(
+            commands(&import),
+            [
+                "google=https://google.com/?q=",
+                "duck=https://duck.com/?q=",
+                "wiki=https://wikipedia.org/?q=",
+                "rust=https://docs.rs/?q="
+            ]
+        );
+        assert_eq!(import.summary().to_string(), "Added: rust\nChanged: wiki");
+    }
+
+    #[test]
+    fn test_add_only() {
+        let import = import(&current(), incoming(), Mode::AddOnly);
+        assert_eq!(
+            commands(&import),
+            [
+                "google=https://google.com/?q=",
+                "duck=https://duck.com/?q=",
+                "wiki=https://w.org/?q=",
+                "rust=https://docs.rs/?q="
+            ]
+        );
+        assert_eq!(import.summary().added(), ["rust"]);
+        assert_eq!(import.summary().kept(), ["wiki"]);
+        assert!(import.summary().changed().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_only_asked_for_differences() {
+        let mut asked = Vec::new();
+        let import = import_with(&current(), incoming(), |current, _| {
+            asked.push(String::from(current.name()));
+            Resolution::Current
+        });
+        assert_eq!(asked, ["wiki"]);
+        assert_eq!(import.summary().kept(), ["wiki"]);
+    }
+
+    #[test]
+    fn test_merge_derived_target() {
+        let current = current();
+        let incoming = crate::executors::from_text_extending(
+            r#"[{"name": "rust", "alias": "r", "extends": "google", "prefix": "site:docs.rs "}]"#,
+            &current,
+        )
+        .unwrap();
+        let import = import(&current, incoming, Mode::Merge);
+        assert_eq!(import.summary().added(), ["rust"]);
+        assert_eq!(
+            import.executors().find("rust").unwrap().command(),
+            "https://google.com/?q="
+        );
+
+        let directory = crate::testing::directory("import_derived");
+        let config = directory.join("config");
+        import.executors().save(&config).unwrap();
+        assert_eq!(
+            &crate::executors::load(&config).unwrap(),
+            import.executors()
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let import = import(&current(), current(), Mode::Replace);
+        assert!(import.summary().is_empty());
+        assert_eq!(import.summary().to_string(), "No changes");
+    }
+
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("merge".parse(), Ok(Mode::Merge));
+        assert_eq!("Add-Only".parse(), Ok(Mode::AddOnly));
+        assert_eq!("REPLACE".parse(), Ok(Mode::Replace));
+        assert!("overwrite".parse::<Mode>().is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Targets that extend other targets
+//!
+//! A target declaring `extends` starts from every field of its base, except `name` and `alias`,
+//! and overrides the fields it sets itself. Overrides are shallow, so setting `http` replaces all
+//! of the base `http` settings. Bases are looked up by name among the targets of the same
+//! configuration first, then among the targets loaded before it
+
+use serde_json::{Map, Value};
+
+use super::executors::Executor;
+use super::validation::Problem;
+
+/// Fields a target never takes from its base
+const NOT_INHERITED: [&str; 3] = ["name", "alias", "extends"];
+
+fn name(value: &Value) -> String {
+    value
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_lowercase)
+        .unwrap_or_default()
+}
+
+fn extends(value: &Value) -> Option<String> {
+    value
+        .get("extends")
+        .and_then(Value::as_str)
+        .map(str::to_lowercase)
+}
+
+/// Returns `true` if any of the targets in `values` extends another one
+pub(crate) fn uses_extends(values: &[Value]) -> bool {
+    values.iter().any(|value| extends(value).is_some())
+}
+
+/// Resolves the bases of every target in `values`, using `bases` for the ones not in `values`
+///
+/// Reports every missing base and every inheritance cycle as a problem of the target that
+/// declares it
+pub(crate) fn resolve(
+    values: &[Value],
+    bases: &[Executor],
+) -> std::result::Result<Vec<Value>, Vec<Problem>> {
+    let mut resolver = Resolver {
+        values,
+        bases,
+        states: vec![State::Pending; values.len()],
+        problems: Vec::new(),
+    };
+    let targets = (0..values.len())
+        .map(|index| resolver.resolve(index, &mut Vec::new()))
+        .collect::<Vec<_>>();
+
+    if resolver.problems.is_empty() {
+        Ok(targets.into_iter().flatten().collect())
+    } else {
+        Err(resolver.problems)
+    }
+}
+
+#[derive(Clone)]
+enum State {
+    Pending,
+    Resolved(Value),
+    Failed,
+}
+
+struct Resolver<'a> {
+    values: &'a [Value],
+    bases: &'a [Executor],
+    states: Vec<State>,
+    problems: Vec<Problem>,
+}
+
+impl Resolver<'_> {
+    /// Resolves the target at `index`, given the `stack` of targets extending it
+    fn resolve(&mut self, index: usize, stack: &mut Vec<usize>) -> Option<Value> {
+        match &self.states[index] {
+            State::Pending => {}
+            State::Resolved(value) => return Some(value.clone()),
+            State::Failed => return None,
+        }
+
+        let value = &self.values[index];
+        let resolved = match extends(value) {
+            None => Some(value.clone()),
+            Some(base) => {
+                stack.push(index);
+                let base = self.base(index, &base, stack);
+                stack.pop();
+                base.map(|base| inherit(base, value))
+            }
+        };
+        self.states[index] = resolved.clone().map_or(State::Failed, State::Resolved);
+        resolved
+    }
+
+    /// Resolves `base`, the target extended by the one at `index`
+    fn base(&mut self, index: usize, base: &str, stack: &mut Vec<usize>) -> Option<Value> {
+        if let Some(base_index) = self.values.iter().position(|value| name(value) == base) {
+            if let Some(start) = stack.iter().position(|other| *other == base_index) {
+                let cycle = stack[start..]
+                    .iter()
+                    .map(|other| name(&self.values[*other]))
+                    .chain(std::iter::once(String::from(base)))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                self.problem(
+                    index,
+                    format!("Inheritance cycle: {cycle}"),
+                    String::from("remove `extends` from one of these targets"),
+                );
+                return None;
+            }
+            return self.resolve(base_index, stack);
+        }
+
+        if let Some(executor) = self.bases.iter().find(|executor| executor.name() == base) {
+            return serde_json::to_value(executor).ok();
+        }
+
+        self.problem(
+            index,
+            format!("Extends unknown target `{base}`"),
+            format!("define `{base}` or remove `extends`"),
+        );
+        None
+    }
+
+    fn problem(&mut self, index: usize, message: String, fix: String) {
+        self.problems.push(
+            Problem::new("extends", message, Some(fix))
+                .in_target(index, &name(&self.values[index])),
+        );
+    }
+}
+
+/// Overlays the fields of `target` over the inherited fields of `base`
+fn inherit(base: Value, target: &Value) -> Value {
+    let mut merged = match base {
+        Value::Object(base) => base,
+        _ => Map::new(),
+    };
+    for field in NOT_INHERITED {
+        merged.remove(field);
+    }
+    merged.insert(String::from("alias"), Value::from(""));
+    if let Value::Object(target) = target {
+        merged.extend(target.clone());
+    }
+    Value::Object(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(json: &str) -> Vec<Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_inherit() {
+        let values = values(
+            r#"[
+                {"name": "rust", "extends": "Google", "prefix": "site:docs.rs"},
+                {"name": "google", "alias": "g", "command": "https://google.com/?q=",
+                 "parser": "GOOGLE", "http": {"proxy": "http://proxy:8080"}},
+                {"name": "crates", "alias": "c", "extends": "rust", "http": {}}
+            ]"#,
+        );
+        assert!(uses_extends(&values));
+
+        let resolved = resolve(&values, &[]).unwrap();
+        assert_eq!(
+            resolved[0],
+            serde_json::json!({
+                "name": "rust", "alias": "", "extends": "Google", "prefix": "site:docs.rs",
+                "command": "https://google.com/?q=", "parser": "GOOGLE",
+                "http": {"proxy": "http://proxy:8080"}
+            })
+        );
+        assert_eq!(resolved[1], values[1]);
+        assert_eq!(resolved[2]["alias"], "c");
+        assert_eq!(resolved[2]["prefix"], "site:docs.rs");
+        assert_eq!(resolved[2]["http"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_external_base() {
+        let bases = crate::executors::from_json(
+            r#"[{"name": "duck", "alias": "d", "command": "https://duck.com/?q=",
+                 "suggestion": "", "parser": "NONE"}]"#,
+        )
+        .unwrap();
+        let bases = bases.iter().cloned().collect::<Vec<_>>();
+
+        let resolved = resolve(&values(r#"[{"name": "ddg", "extends": "duck"}]"#), &bases).unwrap();
+        assert_eq!(resolved[0]["command"], "https://duck.com/?q=");
+        assert_eq!(resolved[0]["alias"], "");
+    }
+
+    #[test]
+    fn test_problems() {
+        let problems = resolve(
+            &values(
+                r#"[
+                    {"name": "a", "extends": "b"},
+                    {"name": "b", "extends": "a"},
+                    {"name": "c", "extends": "c"},
+                    {"name": "d", "extends": "missing"},
+                    {"name": "e", "extends": "a"}
+                ]"#,
+            ),
+            &[],
+        )
+        .unwrap_err();
+
+        let problems = problems
+            .iter()
+            .map(|problem| (problem.target().unwrap(), problem.message()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                ("b", "Inheritance cycle: a -> b -> a"),
+                ("c", "Inheritance cycle: c -> c"),
+                ("d", "Extends unknown target `missing`"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Lenient deserialization of enum-like configuration fields
+//!
+//! Variant names are matched ignoring case, underscores and dashes, so `GOOGLE`, `Google` and
+//! `google` are all the same parser, as are `OPEN_SEARCH` and `opensearch`. Unknown names are
+//! reported along with the list of valid ones. Formats that identify variants by index, such as
+//! the binary configuration of earlier versions, are unaffected
+//!
+//! Enums opt in by deriving serde with `#[serde(remote = "Self")]` and invoking
+//! [`lenient_enum!`](macro.lenient_enum.html), which provides the actual `Serialize` and
+//! `Deserialize` implementations on top of the derived ones
+
+/// Implements `Serialize` and `Deserialize` for an enum that derives them with
+/// `#[serde(remote = "Self")]`, deserializing variant names leniently
+macro_rules! lenient_enum {
+    ($name:ident) => {
+        impl serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                $name::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                $name::deserialize($crate::lenient::Deserializer(deserializer))
+            }
+        }
+    };
+}
+
+pub(crate) use lenient_enum;
+
+pub(crate) fn normalized(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Wraps a deserializer, so that enum variant names are matched leniently
+pub(crate) struct Deserializer<D>(pub(crate) D);
+
+impl<'de, D: serde::Deserializer<'de>> serde::Deserializer<'de> for Deserializer<D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.0.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.0
+            .deserialize_enum(name, variants, Visitor { visitor, variants })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+struct Visitor<V> {
+    visitor: V,
+    variants: &'static [&'static str],
+}
+
+impl<'de, V: serde::de::Visitor<'de>> serde::de::Visitor<'de> for Visitor<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.visitor.expecting(fmt)
+    }
+
+    fn visit_enum<A>(self, data: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: serde::de::EnumAccess<'de>,
+    {
+        self.visitor.visit_enum(EnumAccess {
+            data,
+            variants: self.variants,
+        })
+    }
+}
+
+struct EnumAccess<A> {
+    data: A,
+    variants: &'static [&'static str],
+}
+
+impl<'de, A: serde::de::EnumAccess<'de>> serde::de::EnumAccess<'de> for EnumAccess<A> {
+    type Error = A::Error;
+    type Variant = A::Variant;
+
+    fn variant_seed<S>(self, seed: S) -> std::result::Result<(S::Value, Self::Variant), Self::Error>
+    where
+        S: serde::de::DeserializeSeed<'de>,
+    {
+        self.data.variant_seed(Variant {
+            seed,
+            variants: self.variants,
+        })
+    }
+}
+
+/// Resolves the variant identifier to its declared name before handing it to the derived seed
+struct Variant<S> {
+    seed: S,
+    variants: &'static [&'static str],
+}
+
+impl<'de, S: serde::de::DeserializeSeed<'de>> serde::de::DeserializeSeed<'de> for Variant<S> {
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de, S: serde::de::DeserializeSeed<'de>> serde::de::Visitor<'de> for Variant<S> {
+    type Value = S::Value;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "one of `{}`", self.variants.join("`, `"))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, index: u64) -> std::result::Result<Self::Value, E> {
+        use serde::de::IntoDeserializer;
+
+        self.seed.deserialize(index.into_deserializer())
+    }
+
+    fn visit_str<E: serde::de::Error>(self, name: &str) -> std::result::Result<Self::Value, E> {
+        use serde::de::IntoDeserializer;
+
+        let wanted = normalized(name);
+        let variant = self
+            .variants
+            .iter()
+            .find(|variant| normalized(variant) == wanted)
+            .ok_or_else(|| E::unknown_variant(name, self.variants))?;
+        self.seed.deserialize((*variant).into_deserializer())
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, name: &[u8]) -> std::result::Result<Self::Value, E> {
+        match std::str::from_utf8(name) {
+            Ok(name) => self.visit_str(name),
+            Err(_) => Err(E::invalid_value(serde::de::Unexpected::Bytes(name), &self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(remote = "Self", rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Shape {
+        Circle,
+        OpenPolygon(u8),
+        Square { side: u8 },
+    }
+
+    lenient_enum!(Shape);
+
+    #[test]
+    fn test_names_ignore_case_and_separators() {
+        for name in ["\"CIRCLE\"", "\"circle\"", "\"Circle\""] {
+            assert_eq!(serde_json::from_str::<Shape>(name).unwrap(), Shape::Circle);
+        }
+        for name in ["OPEN_POLYGON", "OpenPolygon", "open-polygon"] {
+            assert_eq!(
+                serde_json::from_str::<Shape>(&format!(r#"{{"{name}": 3}}"#)).unwrap(),
+                Shape::OpenPolygon(3)
+            );
+        }
+        assert_eq!(
+            serde_json::from_str::<Shape>(r#"{"square": {"side": 2}}"#).unwrap(),
+            Shape::Square { side: 2 }
+        );
+    }
+
+    #[test]
+    fn test_unknown_names_list_valid_ones() {
+        let error = serde_json::from_str::<Shape>("\"triangle\"")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown variant `triangle`"));
+        assert!(error.contains("`CIRCLE`, `OPEN_POLYGON`, `SQUARE`"));
+    }
+
+    #[test]
+    fn test_serialization() {
+        assert_eq!(
+            serde_json::to_string(&Shape::OpenPolygon(3)).unwrap(),
+            r#"{"OPEN_POLYGON":3}"#
+        );
+
+        let shapes = vec![
+            Shape::Circle,
+            Shape::OpenPolygon(3),
+            Shape::Square { side: 2 },
+        ];
+        let bytes = bincode::serialize(&shapes).unwrap();
+        assert_eq!(bincode::deserialize::<Vec<Shape>>(&bytes).unwrap(), shapes);
+    }
+}
@@ -1,7 +1,22 @@
 #![deny(warnings, clippy::pedantic, rust_2018_idioms)]
 
+pub mod backup;
+pub mod cache;
+pub mod diff;
 pub mod error;
 pub mod executors;
-mod parser;
+pub mod fetcher;
+pub mod format;
+pub mod http;
+pub mod import;
+mod inheritance;
+mod lenient;
+pub mod parser;
+pub mod process;
+pub mod suggestion;
+#[cfg(test)]
+mod testing;
+pub mod validation;
+pub mod wordlist;
 
 type Result<T = ()> = std::result::Result<T, error::Error>;
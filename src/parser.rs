@@ -1,15 +1,197 @@
 use super::error;
+use super::lenient;
+use super::suggestion::{Origin, Suggestion};
+use super::validation;
 use super::Result;
 
-#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+/// How to parse the responses of a suggestion API
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, PartialEq, Debug)]
+#[serde(remote = "Self", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Parser {
     Google,
     Duck,
     None,
+    // Configurations saved by earlier versions identify the variants above by index, so new
+    // variants go after them
+    OpenSearch,
+    Json(Json),
+    Xml(Xml),
+    Jsonp(Jsonp),
+    Lines,
+    Regex(Regex),
+    /// A parser looked up by name in the [`Registry`](struct.Registry.html)
+    Custom(String),
+}
+
+lenient::lenient_enum!(Parser);
+
+/// A parser of suggestion API responses
+///
+/// Implement this trait to support a suggestion format that no built-in
+/// [`Parser`](enum.Parser.html) understands, and make it available to the configuration through
+/// [`Executors::register_parser`](../executors/struct.Executors.html#method.register_parser)
+pub trait Parse: Send + Sync {
+    /// Extracts the suggestions from the body of a response, in order of relevance
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The decoded body of the response
+    ///
+    /// # Errors
+    ///
+    /// Any error is reported as [`Error(Parse)`](../error/struct.Error.html)
+    fn parse(
+        &self,
+        response: &str,
+    ) -> std::result::Result<Vec<Suggestion>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+impl Parse for Parser {
+    fn parse(
+        &self,
+        response: &str,
+    ) -> std::result::Result<Vec<Suggestion>, Box<dyn std::error::Error + Send + Sync>> {
+        parse(self, &Registry::default(), response).map_err(Into::into)
+    }
+}
+
+/// Maps names to [parsers](trait.Parse.html), so they can be referenced from the configuration as
+/// `{"Custom": "<name>"}`
+///
+/// Names are case insensitive. The built-in `google`, `duck`, `opensearch` and `lines` parsers are
+/// registered by default. Clones share the same parsers, so a parser registered through any of
+/// them is available to all
+#[derive(Clone)]
+pub struct Registry(
+    std::sync::Arc<std::sync::RwLock<std::collections::HashMap<String, std::sync::Arc<dyn Parse>>>>,
+);
+
+impl Default for Registry {
+    fn default() -> Self {
+        let registry = Self(std::sync::Arc::default());
+        registry.register("google", Parser::Google);
+        registry.register("duck", Parser::Duck);
+        registry.register("opensearch", Parser::OpenSearch);
+        registry.register("lines", Parser::Lines);
+        registry
+    }
+}
+
+impl Registry {
+    /// Registers `parser` under `name`, replacing any parser previously registered with that name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name to reference the parser by
+    /// * `parser` - The parser
+    pub fn register<P: Parse + 'static>(&self, name: &str, parser: P) {
+        self.0
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(name.to_lowercase(), std::sync::Arc::new(parser));
+    }
+
+    /// Returns the parser registered under `name`, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name the parser was registered with
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<std::sync::Arc<dyn Parse>> {
+        self.0
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&name.to_lowercase())
+            .cloned()
+    }
+
+    /// Returns the names of all registered parsers, sorted
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self
+            .0
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_tuple("Registry").field(&self.names()).finish()
+    }
+}
+
+/// Generic parser for JSON responses, configured by paths into the response
+///
+/// Paths may either be [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901), such as
+/// `/items/0/name`, or dot separated keys, such as `items.0.name`
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, PartialEq, Debug)]
+pub struct Json {
+    /// Path to the array of suggestions in the response
+    path: String,
+    /// Path to the suggestion text within each element. If not set, elements must be strings
+    #[serde(default)]
+    field: Option<String>,
+    /// Path to a description within each element
+    #[serde(default)]
+    description: Option<String>,
+    /// Path to a direct URL within each element
+    #[serde(default)]
+    url: Option<String>,
+}
+
+/// Generic parser for XML responses, configured by the path to the suggestion elements
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, PartialEq, Debug)]
+pub struct Xml {
+    /// Slash separated names of the elements leading to each suggestion, starting at the root
+    path: String,
+    /// Attribute holding the suggestion text. If not set, the text of the element is used
+    #[serde(default)]
+    attribute: Option<String>,
+}
+
+/// Unwraps JSONP responses, such as `callback([...]);`, before handing them to another parser
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, PartialEq, Debug)]
+pub struct Jsonp {
+    /// Name of the callback wrapping the response. If not set, any callback is accepted
+    #[serde(default)]
+    callback: Option<String>,
+    /// Parser for the unwrapped response
+    parser: Box<Parser>,
+}
+
+/// Extracts suggestions from any text response with a regular expression
+///
+/// Every match is a suggestion. The capture group named `suggestion` is used if present, otherwise
+/// the first capture group, otherwise the whole match
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug)]
+pub struct Regex {
+    pattern: String,
+    /// The pattern, compiled the first time it is needed
+    #[serde(skip)]
+    compiled: std::sync::OnceLock<std::result::Result<regex::Regex, regex::Error>>,
+}
+
+// The compiled regex follows from the pattern
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
 }
 
 #[derive(PartialEq, Debug)]
 struct Google(Vec<String>);
+#[derive(PartialEq, Debug)]
+struct OpenSearch {
+    completions: Vec<String>,
+    descriptions: Vec<String>,
+    urls: Vec<String>,
+}
 #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
 struct Duck(Vec<DuckPhrase>);
 #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
@@ -52,6 +234,84 @@ impl<'a> serde::Deserialize<'a> for Google {
     }
 }
 
+impl<'a> serde::Deserialize<'a> for OpenSearch {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'a>,
+    {
+        struct Visitor;
+
+        impl<'a> serde::de::Visitor<'a> for Visitor {
+            type Value = OpenSearch;
+
+            fn expecting(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    fmt,
+                    "an array with: the query, an array of completions, and optional arrays of descriptions and URLs"
+                )
+            }
+
+            fn visit_seq<V>(self, mut visitor: V) -> std::result::Result<Self::Value, V::Error>
+            where
+                V: serde::de::SeqAccess<'a>,
+            {
+                // Extensions of the format may place anything after the completions
+                fn strings(value: Option<serde_json::Value>) -> Vec<String> {
+                    match value {
+                        Some(serde_json::Value::Array(values)) => values
+                            .into_iter()
+                            .map(|value| match value {
+                                serde_json::Value::String(string) => string,
+                                _ => String::new(),
+                            })
+                            .collect(),
+                        _ => vec![],
+                    }
+                }
+
+                // Ignored the first element (query)
+                visitor.next_element::<serde::de::IgnoredAny>()?;
+
+                let completions = visitor
+                    .next_element::<Vec<String>>()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
+                let descriptions = strings(visitor.next_element()?);
+                let urls = strings(visitor.next_element()?);
+
+                // Ignore the trailing objects, if any
+                while let Some(serde::de::IgnoredAny) = visitor.next_element()? {}
+
+                Ok(OpenSearch {
+                    completions,
+                    descriptions,
+                    urls,
+                })
+            }
+        }
+        deserializer.deserialize_seq(Visitor)
+    }
+}
+
+impl OpenSearch {
+    fn suggestions(self) -> Vec<Suggestion> {
+        let mut descriptions = self.descriptions.into_iter();
+        let mut urls = self.urls.into_iter();
+        ranked(self.completions)
+            .into_iter()
+            .map(|suggestion| {
+                let suggestion = match descriptions.next().filter(|d| !d.is_empty()) {
+                    Some(description) => suggestion.with_description(description),
+                    None => suggestion,
+                };
+                match urls.next().filter(|u| !u.is_empty()) {
+                    Some(url) => suggestion.with_url(url),
+                    None => suggestion,
+                }
+            })
+            .collect()
+    }
+}
+
 impl Duck {
     #[inline]
     fn phrases(self) -> Vec<DuckPhrase> {
@@ -66,19 +326,276 @@ impl DuckPhrase {
     }
 }
 
-pub fn parse(parser: &Parser, result: &str) -> Result<Vec<String>> {
-    match parser {
-        Parser::Google => Ok(serde_json::from_str::<Google>(result)
+fn pointer(path: &str) -> std::borrow::Cow<'_, str> {
+    if path.is_empty() || path.starts_with('/') {
+        path.into()
+    } else {
+        path.split('.')
+            .fold(String::new(), |mut pointer, key| {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                pointer
+            })
+            .into()
+    }
+}
+
+fn lookup<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    value.pointer(&pointer(path))
+}
+
+impl Json {
+    fn text(value: &serde_json::Value) -> Option<String> {
+        match value {
+            serde_json::Value::String(string) => Some(string.clone()),
+            serde_json::Value::Number(number) => Some(number.to_string()),
+            _ => None,
+        }
+    }
+
+    fn parse(&self, result: &str) -> Result<Vec<Suggestion>> {
+        let value = serde_json::from_str::<serde_json::Value>(result)
             .map_err(error::Parse::from)
-            .map_err(error::Error::Parse)?
-            .0),
-        Parser::Duck => Ok(serde_json::from_str::<Duck>(result)
-            .map(Duck::phrases)
+            .map_err(error::Error::Parse)?;
+        let elements = lookup(&value, &self.path)
+            .and_then(serde_json::Value::as_array)
+            .ok_or_else(|| error::Error::Parse(error::Parse::Path(self.path.clone())))?;
+
+        let count = elements.len();
+        Ok(elements
+            .iter()
+            .filter_map(|element| {
+                let extra = |path: &Option<String>| {
+                    path.as_ref()
+                        .and_then(|path| lookup(element, path))
+                        .and_then(Self::text)
+                };
+
+                let text = match &self.field {
+                    Some(path) => lookup(element, path).and_then(Self::text),
+                    None => Self::text(element),
+                }?;
+                let suggestion = Suggestion::new(text, Origin::Remote);
+                let suggestion = match extra(&self.description) {
+                    Some(description) => suggestion.with_description(description),
+                    None => suggestion,
+                };
+                Some(match extra(&self.url) {
+                    Some(url) => suggestion.with_url(url),
+                    None => suggestion,
+                })
+            })
+            .zip((1..=count).rev())
+            .map(|(suggestion, score)| suggestion.with_score(i64::try_from(score).unwrap_or(0)))
+            .collect())
+    }
+}
+
+impl Xml {
+    fn parse(&self, result: &str) -> Result<Vec<Suggestion>> {
+        let document = roxmltree::Document::parse(result)
+            .map_err(error::Parse::from)
+            .map_err(error::Error::Parse)?;
+
+        let mut names = self.path.split('/').filter(|name| !name.is_empty());
+        let root = document.root_element();
+        let mut nodes = match names.next() {
+            Some(name) if root.has_tag_name(name) => vec![root],
+            Some(_) => vec![],
+            None => vec![root],
+        };
+        for name in names {
+            nodes = nodes
+                .into_iter()
+                .flat_map(|node| node.children().filter(|child| child.has_tag_name(name)))
+                .collect();
+        }
+
+        Ok(ranked(
+            nodes
+                .into_iter()
+                .filter_map(|node| match &self.attribute {
+                    Some(attribute) => node.attribute(attribute.as_str()).map(String::from),
+                    None => Some(
+                        node.descendants()
+                            .filter(roxmltree::Node::is_text)
+                            .filter_map(|text| text.text())
+                            .collect::<String>()
+                            .trim()
+                            .to_string(),
+                    ),
+                })
+                .filter(|text| !text.is_empty())
+                .collect(),
+        ))
+    }
+}
+
+impl Jsonp {
+    fn unwrap<'a>(&self, result: &'a str) -> Option<&'a str> {
+        let trimmed = result.trim().trim_start_matches("/**/").trim_start();
+        let (callback, rest) = trimmed.split_at(trimmed.find('(')?);
+        let callback = callback.trim_end();
+
+        let valid = match &self.callback {
+            Some(expected) => callback == expected,
+            None => {
+                !callback.is_empty()
+                    && callback
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '.'))
+            }
+        };
+        if !valid {
+            return None;
+        }
+
+        rest[1..]
+            .trim_end()
+            .trim_end_matches(';')
+            .trim_end()
+            .strip_suffix(')')
+    }
+
+    fn parse(&self, registry: &Registry, result: &str) -> Result<Vec<Suggestion>> {
+        let unwrapped = self
+            .unwrap(result)
+            .ok_or_else(|| error::Error::Parse(error::Parse::Jsonp(self.callback.clone())))?;
+        parse(&self.parser, registry, unwrapped)
+    }
+}
+
+impl Regex {
+    /// Returns the compiled pattern, compiling it only once
+    fn regex(&self) -> std::result::Result<&regex::Regex, regex::Error> {
+        self.compiled
+            .get_or_init(|| regex::Regex::new(&self.pattern))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    fn parse(&self, result: &str) -> Result<Vec<Suggestion>> {
+        let regex = self
+            .regex()
+            .map_err(error::Parse::from)
+            .map_err(error::Error::Parse)?;
+        let named = regex.capture_names().any(|name| name == Some("suggestion"));
+
+        Ok(ranked(
+            regex
+                .captures_iter(result)
+                .filter_map(|captures| {
+                    if named {
+                        captures.name("suggestion")
+                    } else {
+                        captures.get(1).or_else(|| captures.get(0))
+                    }
+                })
+                .map(|capture| capture.as_str().trim())
+                .filter(|text| !text.is_empty())
+                .map(String::from)
+                .collect(),
+        ))
+    }
+}
+
+fn lines(result: &str) -> Vec<Suggestion> {
+    ranked(
+        result
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect(),
+    )
+}
+
+impl Parser {
+    /// Returns the problems with this parser, such as an invalid pattern
+    pub(crate) fn problems(&self) -> Vec<validation::Problem> {
+        match self {
+            Parser::Jsonp(jsonp) => jsonp
+                .parser
+                .problems()
+                .into_iter()
+                .map(|problem| problem.under("JSONP.parser"))
+                .collect(),
+            Parser::Regex(regex) => match regex.regex() {
+                Ok(_) => vec![],
+                Err(error) => vec![validation::Problem::new(
+                    "REGEX.pattern",
+                    format!("Invalid pattern: {error}"),
+                    None,
+                )],
+            },
+            Parser::Json(json) if json.path.is_empty() => vec![validation::Problem::new(
+                "JSON.path",
+                "Empty path",
+                Some(String::from(
+                    "set `path` to where the array of suggestions is, such as `/1`",
+                )),
+            )],
+            // Parsers may be registered after loading, so only the name itself can be checked
+            Parser::Custom(name) if name.trim().is_empty() => vec![validation::Problem::new(
+                "CUSTOM",
+                "Empty parser name",
+                Some(String::from("use the name the parser is registered with")),
+            )],
+            _ => vec![],
+        }
+    }
+}
+
+fn ranked(phrases: Vec<String>) -> Vec<Suggestion> {
+    let count = phrases.len();
+    phrases
+        .into_iter()
+        .zip((1..=count).rev())
+        .map(|(phrase, score)| {
+            Suggestion::new(phrase, Origin::Remote).with_score(i64::try_from(score).unwrap_or(0))
+        })
+        .collect()
+}
+
+fn custom(registry: &Registry, name: &str, result: &str) -> Result<Vec<Suggestion>> {
+    let parser = registry
+        .get(name)
+        .ok_or_else(|| error::Error::Parse(error::Parse::Unknown(String::from(name))))?;
+    parser
+        .parse(result)
+        .map_err(|error| match error.downcast() {
+            Ok(error) => *error,
+            Err(error) => error::Error::Parse(error::Parse::Custom(error)),
+        })
+}
+
+pub(crate) fn parse(parser: &Parser, registry: &Registry, result: &str) -> Result<Vec<Suggestion>> {
+    match parser {
+        Parser::Google => Ok(ranked(
+            serde_json::from_str::<Google>(result)
+                .map_err(error::Parse::from)
+                .map_err(error::Error::Parse)?
+                .0,
+        )),
+        Parser::Duck => Ok(ranked(
+            serde_json::from_str::<Duck>(result)
+                .map(Duck::phrases)
+                .map_err(error::Parse::from)
+                .map_err(error::Error::Parse)?
+                .into_iter()
+                .map(DuckPhrase::phrase)
+                .collect(),
+        )),
+        Parser::OpenSearch => Ok(serde_json::from_str::<OpenSearch>(result)
             .map_err(error::Parse::from)
             .map_err(error::Error::Parse)?
-            .into_iter()
-            .map(DuckPhrase::phrase)
-            .collect()),
+            .suggestions()),
+        Parser::Json(json) => json.parse(result),
+        Parser::Xml(xml) => xml.parse(result),
+        Parser::Jsonp(jsonp) => jsonp.parse(registry, result),
+        Parser::Lines => Ok(lines(result)),
+        Parser::Regex(regex) => regex.parse(result),
+        Parser::Custom(name) => custom(registry, name, result),
         Parser::None => Ok(vec![]),
     }
 }
@@ -87,13 +604,17 @@ pub fn parse(parser: &Parser, result: &str) -> Result<Vec<String>> {
 mod tests {
     use super::*;
 
+    fn parse(parser: &Parser, result: &str) -> Result<Vec<Suggestion>> {
+        super::parse(parser, &Registry::default(), result)
+    }
+
     #[test]
     fn test_google_parsing() {
         let result = r#"["bla",["bladet","blake shelton","black","black panther","blake lively","black mirror","blank","bladkongen","blade runner","blacklist"]]"#;
         let suggestions = parse(&Parser::Google, result).unwrap();
         assert_eq!(suggestions.len(), 10);
-        assert_eq!(suggestions[0], "bladet");
-        assert_eq!(suggestions[9], "blacklist");
+        assert_eq!(suggestions[0].text(), "bladet");
+        assert_eq!(suggestions[9].text(), "blacklist");
     }
 
     #[test]
@@ -101,8 +622,8 @@ mod tests {
         let result = r#"["bla",["black","black widow","blake lively","bladet","blackpink","blaafarvev�rket","blacklist","black panther","black box teater","blazer"],[],{"google:suggestsubtypes":[[433],[433],[433],[433,131],[433,131],[],[433],[433],[],[]]}]"#;
         let suggestions = parse(&Parser::Google, result).unwrap();
         assert_eq!(suggestions.len(), 10);
-        assert_eq!(suggestions[0], "black");
-        assert_eq!(suggestions[9], "blazer");
+        assert_eq!(suggestions[0].text(), "black");
+        assert_eq!(suggestions[9].text(), "blazer");
     }
 
     #[test]
@@ -110,7 +631,348 @@ mod tests {
         let result = r#"[{"phrase":"gopher football"},{"phrase":"gopher"},{"phrase":"gophersports.com"},{"phrase":"gopher football schedule"},{"phrase":"gopher sports"},{"phrase":"gopher 5 winning numbers"},{"phrase":"gopher football score"},{"phrase":"gopher snake"},{"phrase":"gopher hockey"},{"phrase":"gopher volleyball"}]"#;
         let suggestions = parse(&Parser::Duck, result).unwrap();
         assert_eq!(suggestions.len(), 10);
-        assert_eq!(suggestions[0], "gopher football");
-        assert_eq!(suggestions[9], "gopher volleyball");
+        assert_eq!(suggestions[0].text(), "gopher football");
+        assert_eq!(suggestions[9].text(), "gopher volleyball");
+    }
+
+    #[test]
+    fn test_open_search_parsing() {
+        let result = r#"["rust",["Rust","Rust (programming language)","Rust Belt"],["","A multi-paradigm language",""],["https://en.wikipedia.org/wiki/Rust","https://en.wikipedia.org/wiki/Rust_(programming_language)",""]]"#;
+        let suggestions = parse(&Parser::OpenSearch, result).unwrap();
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0].text(), "Rust");
+        assert_eq!(suggestions[0].description(), None);
+        assert_eq!(
+            suggestions[0].url(),
+            Some("https://en.wikipedia.org/wiki/Rust")
+        );
+        assert_eq!(
+            suggestions[1].description(),
+            Some("A multi-paradigm language")
+        );
+        assert_eq!(suggestions[2].text(), "Rust Belt");
+        assert_eq!(suggestions[2].url(), None);
+    }
+
+    #[test]
+    fn test_open_search_parsing_extended() {
+        let result = r#"["bla",["black","black widow","blake lively","bladet","blackpink","blaafarvev�rket","blacklist","black panther","black box teater","blazer"],[],{"google:suggestsubtypes":[[433],[433],[433],[433,131],[433,131],[],[433],[433],[],[]]}]"#;
+        let suggestions = parse(&Parser::OpenSearch, result).unwrap();
+        assert_eq!(suggestions.len(), 10);
+        assert_eq!(suggestions[0].text(), "black");
+        assert!(suggestions
+            .iter()
+            .all(|suggestion| suggestion.url().is_none() && suggestion.description().is_none()));
+    }
+
+    #[test]
+    fn test_open_search_parsing_completions_only() {
+        let result = r#"["bla",["bladet","blake shelton"]]"#;
+        let suggestions = parse(&Parser::OpenSearch, result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[1].text(), "blake shelton");
+    }
+
+    fn xml(path: &str, attribute: Option<&str>) -> Parser {
+        Parser::Xml(Xml {
+            path: String::from(path),
+            attribute: attribute.map(String::from),
+        })
+    }
+
+    #[test]
+    fn test_xml_parsing_attribute() {
+        let result = r#"<?xml version="1.0"?><toplevel><CompleteSuggestion><suggestion data="black"/></CompleteSuggestion><CompleteSuggestion><suggestion data="black widow"/></CompleteSuggestion><CompleteSuggestion><suggestion data="blake lively"/></CompleteSuggestion></toplevel>"#;
+        let suggestions = parse(
+            &xml("toplevel/CompleteSuggestion/suggestion", Some("data")),
+            result,
+        )
+        .unwrap();
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0].text(), "black");
+        assert_eq!(suggestions[2].text(), "blake lively");
+        assert!(suggestions[0].score() > suggestions[2].score());
+    }
+
+    #[test]
+    fn test_xml_parsing_text() {
+        let result = r"
+            <results>
+                <result><title>gopher football</title><rank>1</rank></result>
+                <result><title>  gopher <b>snake</b>  </title></result>
+                <result><title/></result>
+                <other><title>ignored</title></other>
+            </results>
+        ";
+        let suggestions = parse(&xml("/results/result/title", None), result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].text(), "gopher football");
+        assert_eq!(suggestions[1].text(), "gopher snake");
+    }
+
+    #[test]
+    fn test_xml_parsing_wrong_root() {
+        let result = r#"<toplevel><suggestion data="black"/></toplevel>"#;
+        let suggestions = parse(&xml("other/suggestion", Some("data")), result).unwrap();
+        assert!(suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_xml_parsing_malformed() {
+        let result = r#"<toplevel><suggestion data="black"></toplevel>"#;
+        assert!(matches!(
+            parse(&xml("toplevel/suggestion", Some("data")), result),
+            Err(error::Error::Parse(error::Parse::Xml(_)))
+        ));
+    }
+
+    fn jsonp(callback: Option<&str>, parser: Parser) -> Parser {
+        Parser::Jsonp(Jsonp {
+            callback: callback.map(String::from),
+            parser: Box::new(parser),
+        })
+    }
+
+    #[test]
+    fn test_jsonp_parsing_youtube() {
+        let result = r#"window.google.ac.h(["rust",[["rust",0,[512,433]],["rust game",0,[512]],["rust console",0,[512]]],{"k":1,"q":"abc"}])"#;
+        let parser = jsonp(Some("window.google.ac.h"), json("/1", Some("/0")));
+        let suggestions = parse(&parser, result).unwrap();
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0].text(), "rust");
+        assert_eq!(suggestions[2].text(), "rust console");
+    }
+
+    #[test]
+    fn test_jsonp_parsing_any_callback() {
+        let result = "/**/ jQuery_123.cb ( [\"bla\",[\"bladet\",\"blake shelton\"]] ) ;\n";
+        let suggestions = parse(&jsonp(None, Parser::Google), result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].text(), "bladet");
+    }
+
+    #[test]
+    fn test_jsonp_parsing_wrong_callback() {
+        let result = r#"other(["bla",["bladet"]])"#;
+        assert!(matches!(
+            parse(&jsonp(Some("callback"), Parser::Google), result),
+            Err(error::Error::Parse(error::Parse::Jsonp(Some(_))))
+        ));
+    }
+
+    #[test]
+    fn test_jsonp_parsing_bare_json() {
+        let result = r#"["bla",["bladet"]]"#;
+        assert!(matches!(
+            parse(&jsonp(None, Parser::Google), result),
+            Err(error::Error::Parse(error::Parse::Jsonp(None)))
+        ));
+    }
+
+    #[test]
+    fn test_lines_parsing() {
+        let result = "gopher football\r\n  gopher  \n\n\t\ngopher snake";
+        let suggestions = parse(&Parser::Lines, result).unwrap();
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0].text(), "gopher football");
+        assert_eq!(suggestions[1].text(), "gopher");
+        assert_eq!(suggestions[2].text(), "gopher snake");
+    }
+
+    fn regex(pattern: &str) -> Parser {
+        Parser::Regex(Regex {
+            pattern: String::from(pattern),
+            compiled: std::sync::OnceLock::new(),
+        })
+    }
+
+    #[test]
+    fn test_regex_is_compiled_once() {
+        let Parser::Regex(regex) = regex(r"\w+") else {
+            unreachable!()
+        };
+        assert!(regex.parse("a b").is_ok());
+        assert!(Parser::Regex(regex.clone()).problems().is_empty());
+        assert!(std::ptr::eq(regex.regex().unwrap(), regex.regex().unwrap()));
+        assert!(regex.compiled.get().is_some());
+    }
+
+    #[test]
+    fn test_regex_parsing_group() {
+        let result =
+            r#"<ul><li class="s">black</li><li class="s">black widow</li><li>ignored</li></ul>"#;
+        let suggestions = parse(&regex(r#"<li class="s">([^<]*)</li>"#), result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].text(), "black");
+        assert_eq!(suggestions[1].text(), "black widow");
+    }
+
+    #[test]
+    fn test_regex_parsing_named_group() {
+        let result = "1: alpha\n2: beta\n";
+        let suggestions = parse(&regex(r"(\d+): (?P<suggestion>\w+)"), result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].text(), "alpha");
+        assert_eq!(suggestions[1].text(), "beta");
+    }
+
+    #[test]
+    fn test_regex_parsing_whole_match() {
+        let result = "ERR-001, ERR-042 and WARN-7";
+        let suggestions = parse(&regex(r"ERR-\d+"), result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[1].text(), "ERR-042");
+    }
+
+    #[test]
+    fn test_regex_parsing_invalid_pattern() {
+        assert!(matches!(
+            parse(&regex("(unclosed"), ""),
+            Err(error::Error::Parse(error::Parse::Regex(_)))
+        ));
+    }
+
+    fn json(path: &str, field: Option<&str>) -> Parser {
+        Parser::Json(Json {
+            path: String::from(path),
+            field: field.map(String::from),
+            description: None,
+            url: None,
+        })
+    }
+
+    #[test]
+    fn test_json_parsing_pointer() {
+        let result = r#"["bla",["black","black widow","blake lively"]]"#;
+        let suggestions = parse(&json("/1", None), result).unwrap();
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0].text(), "black");
+        assert_eq!(suggestions[2].text(), "blake lively");
+    }
+
+    #[test]
+    fn test_json_parsing_field() {
+        let result = r#"[{"phrase":"gopher football"},{"phrase":"gopher"},{"other":"ignored"}]"#;
+        let suggestions = parse(&json("", Some("phrase")), result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].text(), "gopher football");
+        assert_eq!(suggestions[1].text(), "gopher");
+    }
+
+    #[test]
+    fn test_json_parsing_dotted_path() {
+        let result = r#"{"data":{"items":[{"title":{"text":"first"},"link":"https://first"},{"title":{"text":"second"}}]}}"#;
+        let parser = Parser::Json(Json {
+            path: String::from("data.items"),
+            field: Some(String::from("/title/text")),
+            description: None,
+            url: Some(String::from("link")),
+        });
+        let suggestions = parse(&parser, result).unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].text(), "first");
+        assert_eq!(suggestions[0].url(), Some("https://first"));
+        assert_eq!(suggestions[1].text(), "second");
+        assert_eq!(suggestions[1].url(), None);
+    }
+
+    #[test]
+    fn test_json_parsing_missing_path() {
+        let result = r#"{"data":{}}"#;
+        assert!(matches!(
+            parse(&json("data.items", None), result),
+            Err(error::Error::Parse(error::Parse::Path(_)))
+        ));
+    }
+
+    #[test]
+    fn test_names_are_case_insensitive() {
+        for (name, parser) in [
+            ("GOOGLE", Parser::Google),
+            ("duck", Parser::Duck),
+            ("OPENSEARCH", Parser::OpenSearch),
+            ("open_search", Parser::OpenSearch),
+            ("None", Parser::None),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<Parser>(&format!("\"{name}\"")).unwrap(),
+                parser
+            );
+        }
+
+        let error = serde_json::from_str::<Parser>("\"BING\"")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown variant `BING`, expected one of `GOOGLE`, `DUCK`"));
+    }
+
+    #[test]
+    fn test_json_config() {
+        let parser: Parser =
+            serde_json::from_str(r#"{"Json":{"path":"/1","field":"/0"}}"#).unwrap();
+        assert_eq!(parser, json("/1", Some("/0")));
+    }
+
+    #[test]
+    fn test_remote_scoring() {
+        let result = r#"["bla",["first","second","third"]]"#;
+        let suggestions = parse(&Parser::Google, result).unwrap();
+        assert!(suggestions
+            .iter()
+            .all(|suggestion| suggestion.origin() == Origin::Remote));
+        assert!(suggestions[0].score() > suggestions[1].score());
+        assert!(suggestions[1].score() > suggestions[2].score());
+    }
+
+    struct Reversed;
+
+    impl Parse for Reversed {
+        fn parse(
+            &self,
+            response: &str,
+        ) -> std::result::Result<Vec<Suggestion>, Box<dyn std::error::Error + Send + Sync>>
+        {
+            if response.is_empty() {
+                return Err("empty response".into());
+            }
+            Ok(response
+                .split(',')
+                .rev()
+                .map(|text| Suggestion::new(text, Origin::Remote))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_custom_parsing() {
+        let registry = Registry::default();
+        registry.register("Reversed", Reversed);
+        let parser = Parser::Custom(String::from("REVERSED"));
+
+        let suggestions = super::parse(&parser, &registry, "a,b,c").unwrap();
+        assert_eq!(suggestions[0].text(), "c");
+        assert!(matches!(
+            super::parse(&parser, &registry, ""),
+            Err(error::Error::Parse(error::Parse::Custom(_)))
+        ));
+        assert!(matches!(
+            parse(&parser, "a,b,c"),
+            Err(error::Error::Parse(error::Parse::Unknown(_)))
+        ));
+    }
+
+    #[test]
+    fn test_builtin_parsers_registered() {
+        let registry = Registry::default();
+        assert_eq!(registry.names(), ["duck", "google", "lines", "opensearch"]);
+
+        let result = r#"["bla",["first","second"]]"#;
+        let suggestions =
+            super::parse(&Parser::Custom(String::from("Google")), &registry, result).unwrap();
+        assert_eq!(suggestions[1].text(), "second");
+        assert!(matches!(
+            super::parse(&Parser::Custom(String::from("google")), &registry, "nope"),
+            Err(error::Error::Parse(error::Parse::Json(_)))
+        ));
     }
 }
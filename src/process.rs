@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+
+use super::error;
+use super::http;
+use super::lenient;
+use super::validation;
+use super::Result;
+
+const DEFAULT_TIMEOUT: u64 = 2000;
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// How the query is handed to a suggestion [`Process`](struct.Process.html)
+#[derive(
+    Serialize, Deserialize, schemars::JsonSchema, Copy, Clone, PartialEq, Eq, Debug, Default,
+)]
+#[serde(remote = "Self", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Input {
+    /// The query is appended to the arguments
+    #[default]
+    Argument,
+    /// The query is written to the standard input, followed by a new line
+    Stdin,
+}
+
+lenient::lenient_enum!(Input);
+
+/// A local program that provides suggestions on its standard output
+///
+/// The output is parsed by the `parser` of the target, so programs would usually print one
+/// suggestion per line, to be parsed by `LINES`, or JSON, to be parsed by `JSON`
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct Process {
+    /// Program to run, looked up in `PATH` if not a path
+    program: String,
+    /// Arguments always passed to the program, before the query
+    #[serde(default)]
+    args: Vec<String>,
+    /// How the query is handed to the program
+    #[serde(default)]
+    input: Input,
+    /// How long, in milliseconds, the program may run before being killed. Defaults to 2000
+    #[serde(default)]
+    timeout: Option<u64>,
+}
+
+fn drain<R: std::io::Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut bytes)?;
+        }
+        Ok(bytes)
+    })
+}
+
+fn error(program: &str, error: error::Process) -> error::Error {
+    error::Error::Process(String::from(program), error)
+}
+
+impl Process {
+    /// Returns the problems with this program, such as an empty name
+    pub(crate) fn problems(&self) -> Vec<validation::Problem> {
+        if self.program.trim().is_empty() {
+            vec![validation::Problem::new(
+                "program",
+                "Empty program",
+                Some(String::from(
+                    "set `program` to the name or path of the program to run",
+                )),
+            )]
+        } else {
+            vec![]
+        }
+    }
+
+    fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout.unwrap_or(DEFAULT_TIMEOUT))
+    }
+
+    /// Runs the program for `query`, returning its standard output decoded with `charset`, or as
+    /// UTF-8 if not set
+    ///
+    /// The program is killed if it does not finish within its timeout
+    pub(crate) fn run(&self, query: &str, charset: Option<&str>) -> Result<String> {
+        use std::io::Write;
+
+        let mut command = std::process::Command::new(&self.program);
+        command
+            .args(&self.args)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        match self.input {
+            Input::Argument => command.arg(query).stdin(std::process::Stdio::null()),
+            Input::Stdin => command.stdin(std::process::Stdio::piped()),
+        };
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| error(&self.program, error::Process::Spawn(e)))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // Programs are free to ignore their input
+            writeln!(stdin, "{query}").ok();
+        }
+
+        // Pipes are drained while waiting, so that a chatty program cannot block on a full pipe
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+
+        let deadline = std::time::Instant::now() + self.timeout();
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .map_err(|e| error(&self.program, error::Process::Io(e)))?
+            {
+                break status;
+            }
+
+            if std::time::Instant::now() >= deadline {
+                // The program may have exited in the meantime
+                child.kill().ok();
+                child.wait().ok();
+                return Err(error(
+                    &self.program,
+                    error::Process::Timeout(self.timeout.unwrap_or(DEFAULT_TIMEOUT)),
+                ));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        let join = |pipe: std::thread::JoinHandle<std::io::Result<Vec<u8>>>| {
+            pipe.join()
+                .unwrap_or_else(|_| Ok(Vec::new()))
+                .map_err(|e| error(&self.program, error::Process::Io(e)))
+        };
+        let stdout = join(stdout)?;
+        let stderr = join(stderr)?;
+
+        if !status.success() {
+            return Err(error(
+                &self.program,
+                error::Process::Status(status, String::from_utf8_lossy(&stderr).trim().to_string()),
+            ));
+        }
+
+        http::decode_as(&stdout, charset)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str, input: Input, timeout: Option<u64>) -> Process {
+        Process {
+            program: String::from("sh"),
+            args: vec![String::from("-c"), String::from(script), String::from("sh")],
+            input,
+            timeout,
+        }
+    }
+
+    #[test]
+    fn test_query_as_argument() {
+        let process = shell(r#"echo "$1 one"; echo "$1 two""#, Input::Argument, None);
+        assert_eq!(process.run("rust", None).unwrap(), "rust one\nrust two\n");
+    }
+
+    #[test]
+    fn test_query_on_stdin() {
+        let process = shell("read query; echo \"[\\\"$query\\\"]\"", Input::Stdin, None);
+        assert_eq!(process.run("rust", None).unwrap(), "[\"rust\"]\n");
+    }
+
+    #[test]
+    fn test_output_charset() {
+        let process = shell(r"printf 'caf\351'", Input::Argument, None);
+        assert_eq!(process.run("", Some("latin1")).unwrap(), "café");
+    }
+
+    #[test]
+    fn test_timeout() {
+        let process = shell("sleep 5", Input::Argument, Some(100));
+        let start = std::time::Instant::now();
+        assert!(matches!(
+            process.run("rust", None),
+            Err(error::Error::Process(_, error::Process::Timeout(100)))
+        ));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_failure() {
+        let process = shell("echo broken >&2; exit 3", Input::Argument, None);
+        match process.run("rust", None) {
+            Err(error::Error::Process(program, error::Process::Status(status, stderr))) => {
+                assert_eq!(program, "sh");
+                assert_eq!(status.code(), Some(3));
+                assert_eq!(stderr, "broken");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_missing_program() {
+        let process = Process {
+            program: String::from("vai-no-such-program"),
+            args: vec![],
+            input: Input::Argument,
+            timeout: None,
+        };
+        assert!(matches!(
+            process.run("rust", None),
+            Err(error::Error::Process(_, error::Process::Spawn(_)))
+        ));
+    }
+}
@@ -0,0 +1,305 @@
+use serde::{Deserialize, Serialize};
+
+use super::error;
+
+/// Where a [`Suggestion`](struct.Suggestion.html) came from
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Origin {
+    /// Previously executed queries for the target
+    History,
+    /// The suggestion API of the target, or the local program providing its suggestions
+    Remote,
+}
+
+/// A single suggestion for a query
+///
+/// Besides the suggested `text`, carries where it came from, how relevant it is and whatever
+/// extra data the source provided
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Suggestion {
+    text: String,
+    origin: Origin,
+    score: i64,
+    description: Option<String>,
+    url: Option<String>,
+}
+
+impl Suggestion {
+    /// Creates a new suggestion with no score, description or URL
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The suggested query
+    /// * `origin` - Where the suggestion came from
+    #[must_use]
+    pub fn new<S: Into<String>>(text: S, origin: Origin) -> Self {
+        Self {
+            text: text.into(),
+            origin,
+            score: 0,
+            description: None,
+            url: None,
+        }
+    }
+
+    /// Sets the relevance score, where higher is more relevant
+    #[must_use]
+    pub fn with_score(self, score: i64) -> Self {
+        Self { score, ..self }
+    }
+
+    /// Sets a human readable description of the suggestion
+    #[must_use]
+    pub fn with_description<S: Into<String>>(self, description: S) -> Self {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    /// Sets a URL to be opened directly, instead of querying the target
+    #[must_use]
+    pub fn with_url<S: Into<String>>(self, url: S) -> Self {
+        Self {
+            url: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Returns the suggested query
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns where the suggestion came from
+    #[must_use]
+    pub fn origin(&self) -> Origin {
+        self.origin
+    }
+
+    /// Returns the relevance score
+    ///
+    /// Scores are only comparable between suggestions of the same [`Origin`](enum.Origin.html)
+    #[must_use]
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    /// Returns the description, if the source provided one
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the direct URL, if the source provided one
+    #[must_use]
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Consumes the suggestion, returning the suggested query
+    #[must_use]
+    pub fn into_text(self) -> String {
+        self.text
+    }
+}
+
+/// How to interleave history and remote suggestions when merging them
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Ranking {
+    /// All history suggestions, followed by all remote suggestions
+    HistoryFirst,
+    /// All remote suggestions, followed by all history suggestions
+    RemoteFirst,
+    /// Alternate between history and remote suggestions, starting with history
+    #[default]
+    Interleaved,
+}
+
+impl std::str::FromStr for Ranking {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "history" | "history_first" => Ok(Self::HistoryFirst),
+            "remote" | "remote_first" => Ok(Self::RemoteFirst),
+            "interleaved" => Ok(Self::Interleaved),
+            _ => Err(format!("Unknown ranking: {input}")),
+        }
+    }
+}
+
+/// What happened when the suggestion API of a target was consulted
+#[derive(Debug)]
+pub enum Status {
+    /// The target has no suggestion API or the query was too short
+    Skipped,
+    /// The suggestions were fetched from the suggestion API
+    Fetched,
+    /// The suggestions were served from the cache
+    Cached,
+    /// The network was not used, so only previously cached suggestions, if any, were used
+    Offline,
+    /// The suggestion API failed, so only previously cached suggestions, if any, were used
+    Failed(error::Error),
+}
+
+/// Ranked [`Suggestions`](struct.Suggestion.html) along with the [`Status`](enum.Status.html) of
+/// the suggestion API
+#[derive(Debug)]
+pub struct Suggestions {
+    suggestions: Vec<Suggestion>,
+    status: Status,
+}
+
+impl Suggestions {
+    pub(crate) fn new(suggestions: Vec<Suggestion>, status: Status) -> Self {
+        Self {
+            suggestions,
+            status,
+        }
+    }
+
+    /// Returns the suggestions, in order of relevance
+    #[must_use]
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
+    }
+
+    /// Returns what happened when consulting the suggestion API
+    #[must_use]
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Returns `true` if the suggestion API could not be used, be it by choice or by failure
+    #[must_use]
+    pub fn is_offline(&self) -> bool {
+        matches!(self.status, Status::Offline | Status::Failed(_))
+    }
+
+    /// Consumes this instance, returning the suggestions in order of relevance
+    #[must_use]
+    pub fn into_vec(self) -> Vec<Suggestion> {
+        self.suggestions
+    }
+}
+
+fn normalized(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Merges `history` and `remote` into a single list of up to `count` entries
+///
+/// Each list is expected to be sorted by relevance. Entries that only differ by case or whitespace
+/// are considered the same and only the first one, according to `ranking`, is kept
+pub(crate) fn merge(
+    history: Vec<Suggestion>,
+    remote: Vec<Suggestion>,
+    ranking: Ranking,
+    count: usize,
+) -> Vec<Suggestion> {
+    let ordered: Box<dyn Iterator<Item = Suggestion>> = match ranking {
+        Ranking::HistoryFirst => Box::new(history.into_iter().chain(remote)),
+        Ranking::RemoteFirst => Box::new(remote.into_iter().chain(history)),
+        Ranking::Interleaved => {
+            let mut history = history.into_iter();
+            let mut remote = remote.into_iter();
+            Box::new(
+                std::iter::from_fn(move || match (history.next(), remote.next()) {
+                    (None, None) => None,
+                    pair => Some(pair),
+                })
+                .flat_map(|(history, remote)| history.into_iter().chain(remote)),
+            )
+        }
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    ordered
+        .filter(|suggestion| seen.insert(normalized(suggestion.text())))
+        .take(count)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(texts: &[&str]) -> Vec<Suggestion> {
+        texts
+            .iter()
+            .map(|text| Suggestion::new(*text, Origin::History))
+            .collect()
+    }
+
+    fn remote(texts: &[&str]) -> Vec<Suggestion> {
+        texts
+            .iter()
+            .map(|text| Suggestion::new(*text, Origin::Remote))
+            .collect()
+    }
+
+    fn texts(suggestions: &[Suggestion]) -> Vec<&str> {
+        suggestions.iter().map(Suggestion::text).collect()
+    }
+
+    #[test]
+    fn test_merge_rankings() {
+        let merge_with = |ranking| {
+            merge(
+                history(&["h1", "h2", "h3"]),
+                remote(&["r1"]),
+                ranking,
+                usize::MAX,
+            )
+        };
+        assert_eq!(
+            texts(&merge_with(Ranking::HistoryFirst)),
+            ["h1", "h2", "h3", "r1"]
+        );
+        assert_eq!(
+            texts(&merge_with(Ranking::RemoteFirst)),
+            ["r1", "h1", "h2", "h3"]
+        );
+        assert_eq!(
+            texts(&merge_with(Ranking::Interleaved)),
+            ["h1", "r1", "h2", "h3"]
+        );
+    }
+
+    #[test]
+    fn test_merge_deduplicates() {
+        let merged = merge(
+            history(&["Rust  Lang", "tokio"]),
+            remote(&[" rust lang", "serde", "TOKIO "]),
+            Ranking::RemoteFirst,
+            usize::MAX,
+        );
+        assert_eq!(texts(&merged), [" rust lang", "serde", "TOKIO "]);
+        assert!(merged.iter().all(|s| s.origin() == Origin::Remote));
+    }
+
+    #[test]
+    fn test_merge_limits_count() {
+        let merged = merge(
+            history(&["h1", "h2"]),
+            remote(&["r1", "r2"]),
+            Ranking::Interleaved,
+            3,
+        );
+        assert_eq!(texts(&merged), ["h1", "r1", "h2"]);
+    }
+
+    #[test]
+    fn test_ranking_from_str() {
+        assert_eq!("History".parse(), Ok(Ranking::HistoryFirst));
+        assert_eq!("remote_first".parse(), Ok(Ranking::RemoteFirst));
+        assert_eq!("INTERLEAVED".parse(), Ok(Ranking::Interleaved));
+        assert!("best".parse::<Ranking>().is_err());
+    }
+}
@@ -0,0 +1,26 @@
+//! Fixtures shared by the unit tests
+
+use super::executors::{self, Executors};
+
+/// Returns an empty directory named after `name`, unique to this test run
+pub(crate) fn directory(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("vai_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
+
+/// Builds a configuration with a target for each `(name, host)` pair, without suggestions
+pub(crate) fn executors(targets: &[(&str, &str)]) -> Executors {
+    let json = targets
+        .iter()
+        .map(|(name, host)| {
+            format!(
+                r#"{{"name": "{name}", "alias": "", "command": "https://{host}/?q=",
+                    "suggestion": "", "parser": "NONE"}}"#
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    executors::from_json(&format!("[{json}]")).unwrap()
+}
@@ -0,0 +1,538 @@
+//! Validation of configurations, reporting every problem found along with where it is
+
+use super::lenient;
+
+/// A problem found in a configuration
+///
+/// Problems found while importing JSON also carry the line and column they refer to, as do
+/// TOML and YAML syntax errors
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Problem {
+    index: Option<usize>,
+    target: Option<String>,
+    field: String,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+    fix: Option<String>,
+}
+
+impl Problem {
+    pub(crate) fn new<S: Into<String>>(field: &str, message: S, fix: Option<String>) -> Self {
+        Self {
+            index: None,
+            target: None,
+            field: String::from(field),
+            line: None,
+            column: None,
+            message: message.into(),
+            fix,
+        }
+    }
+
+    /// Nests the field of this problem under `parent`
+    #[must_use]
+    pub(crate) fn under(self, parent: &str) -> Self {
+        Self {
+            field: if self.field.is_empty() {
+                String::from(parent)
+            } else {
+                format!("{parent}.{}", self.field)
+            },
+            ..self
+        }
+    }
+
+    /// Attributes this problem to the target at `index`, named `name`
+    #[must_use]
+    pub(crate) fn in_target(self, index: usize, name: &str) -> Self {
+        Self {
+            index: Some(index),
+            target: Some(if name.is_empty() {
+                format!("#{}", index + 1)
+            } else {
+                String::from(name)
+            }),
+            ..self
+        }
+    }
+
+    /// Returns the name of the target, or its position as `#<n>` if it has no name
+    #[must_use]
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Returns the path to the field, with nested fields separated by dots
+    #[must_use]
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns the line of the JSON the problem was found at, if known
+    #[must_use]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Returns the column of the JSON the problem was found at, if known
+    #[must_use]
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Returns what is wrong
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns how the problem could be fixed, if there is an obvious way
+    #[must_use]
+    pub fn fix(&self) -> Option<&str> {
+        self.fix.as_deref()
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(fmt, "line {line}, column {column}: ")?;
+        }
+        if let Some(target) = &self.target {
+            write!(fmt, "target `{target}`, ")?;
+        }
+        if !self.field.is_empty() {
+            write!(fmt, "field `{}`: ", self.field)?;
+        }
+        write!(fmt, "{}", self.message)?;
+        if let Some(fix) = &self.fix {
+            write!(fmt, ". Fix: {fix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists `problems`, one per line
+pub(crate) fn list(problems: &[Problem]) -> String {
+    problems.iter().fold(String::new(), |mut list, problem| {
+        list.push_str("\n  ");
+        list.push_str(&problem.to_string());
+        list
+    })
+}
+
+/// Returns a problem if `url` is not an http or https URL
+pub(crate) fn url(field: &str, url: &str) -> Option<Problem> {
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_lowercase());
+    match scheme.as_deref() {
+        Some("http" | "https") => None,
+        Some(scheme) => Some(Problem::new(
+            field,
+            format!("Unsupported URL scheme `{scheme}`"),
+            Some(String::from("use an http or https URL")),
+        )),
+        None => Some(Problem::new(
+            field,
+            format!("`{url}` is not a URL"),
+            Some(format!("use a full URL, such as `https://{url}`")),
+        )),
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+/// Where a target and each of its fields are in the JSON
+#[derive(Debug)]
+struct Element {
+    start: Position,
+    end: Option<Position>,
+    name: Option<String>,
+    keys: Vec<(String, Position)>,
+}
+
+impl Element {
+    fn key_before(&self, position: Position) -> Option<&(String, Position)> {
+        self.keys.iter().rev().find(|(_, key)| *key <= position)
+    }
+
+    /// Finds `field`, or the closest parent of it that is present
+    fn locate(&self, field: &str) -> Position {
+        let mut path = normalized(field);
+        loop {
+            if let Some((_, position)) = self.keys.iter().find(|(key, _)| normalized(key) == path) {
+                return *position;
+            }
+            match path.rfind('.') {
+                Some(index) => path.truncate(index),
+                None => return self.start,
+            }
+        }
+    }
+}
+
+fn normalized(path: &str) -> String {
+    path.split('.')
+        .map(lenient::normalized)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// A forgiving scanner that records where each target and field of a JSON configuration starts
+///
+/// Scanning stops at the first syntax error, keeping whatever was found until then
+struct Scanner<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    position: Position,
+}
+
+impl Scanner<'_> {
+    fn scan(json: &str) -> Vec<Element> {
+        let mut scanner = Scanner {
+            chars: json.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+        };
+        let mut elements = Vec::new();
+        scanner.elements(&mut elements);
+        elements
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.next();
+        }
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        (self.peek()? == expected).then(|| self.next()).flatten()?;
+        Some(())
+    }
+
+    fn elements(&mut self, elements: &mut Vec<Element>) -> Option<()> {
+        self.expect('[')?;
+        if self.peek()? == ']' {
+            return Some(());
+        }
+        loop {
+            self.peek()?;
+            elements.push(Element {
+                start: self.position,
+                end: None,
+                name: None,
+                keys: Vec::new(),
+            });
+            let element = elements.last_mut()?;
+            self.value(element, "")?;
+            element.end = Some(self.position);
+
+            match self.next_token()? {
+                ',' => {}
+                ']' => return Some(()),
+                _ => return None,
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Option<char> {
+        self.peek()?;
+        self.next()
+    }
+
+    /// Scans a value at `path`, keeping the name of the target when found
+    fn value(&mut self, element: &mut Element, path: &str) -> Option<()> {
+        match self.peek()? {
+            '{' => {
+                self.next();
+                if self.peek()? == '}' {
+                    self.next();
+                    return Some(());
+                }
+                loop {
+                    if self.peek()? != '"' {
+                        return None;
+                    }
+                    let position = self.position;
+                    let key = self.string()?;
+                    let child = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    element.keys.push((child.clone(), position));
+                    self.expect(':')?;
+                    self.value(element, &child)?;
+                    match self.next_token()? {
+                        ',' => {}
+                        '}' => return Some(()),
+                        _ => return None,
+                    }
+                }
+            }
+            '[' => {
+                self.next();
+                if self.peek()? == ']' {
+                    self.next();
+                    return Some(());
+                }
+                loop {
+                    self.value(element, path)?;
+                    match self.next_token()? {
+                        ',' => {}
+                        ']' => return Some(()),
+                        _ => return None,
+                    }
+                }
+            }
+            '"' => {
+                let string = self.string()?;
+                if path == "name" {
+                    element.name = Some(string);
+                }
+                Some(())
+            }
+            _ => {
+                while self
+                    .chars
+                    .peek()
+                    .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '}'))
+                {
+                    self.next();
+                }
+                Some(())
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Some(string),
+                '\\' => {
+                    let escaped = self.next()?;
+                    string.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                }
+                c => string.push(c),
+            }
+        }
+    }
+}
+
+/// Adds the line and column of each problem, as found in `json`
+pub(crate) fn locate(json: &str, problems: Vec<Problem>) -> Vec<Problem> {
+    let elements = Scanner::scan(json);
+    problems
+        .into_iter()
+        .map(
+            |problem| match problem.index.and_then(|index| elements.get(index)) {
+                Some(element) => {
+                    let position = element.locate(&problem.field);
+                    Problem {
+                        line: Some(position.line),
+                        column: Some(position.column),
+                        ..problem
+                    }
+                }
+                None => problem,
+            },
+        )
+        .collect()
+}
+
+/// Describes a JSON deserialization error as a problem of the target and field it happened in
+pub(crate) fn deserialization(json: &str, error: &serde_json::Error) -> Problem {
+    let position = Position {
+        line: error.line(),
+        column: error.column(),
+    };
+    let message = error.to_string();
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(message, _)| message);
+
+    let elements = Scanner::scan(json);
+    let found = elements.iter().enumerate().rev().find(|(_, element)| {
+        element.start <= position && element.end.is_none_or(|end| position <= end)
+    });
+
+    let mut problem = Problem::new("", capitalized(message), None);
+    if let Some((index, element)) = found {
+        // Missing fields are reported at the end of the target, so name them explicitly
+        let missing = message
+            .strip_prefix("missing field `")
+            .and_then(|field| field.strip_suffix('`'));
+        problem = match (missing, element.key_before(position)) {
+            (Some(field), _) => Problem {
+                fix: Some(format!("add `{field}` to the target")),
+                ..problem.under(field)
+            },
+            (None, Some((field, _))) => problem.under(field),
+            (None, None) => problem,
+        }
+        .in_target(index, element.name.as_deref().unwrap_or_default());
+    }
+
+    Problem {
+        line: Some(position.line),
+        column: Some(position.column),
+        ..problem
+    }
+}
+
+/// Builds the [problem](struct.Problem.html) for a configuration that could not be deserialized
+/// from a format other than json, whose errors already tell where they are
+pub(crate) fn deserialization_at(
+    target: Option<(usize, &str)>,
+    field: &str,
+    message: &str,
+    position: Option<(usize, usize)>,
+) -> Problem {
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message, |(message, _)| message);
+    let problem = Problem::new(field, capitalized(message), None);
+    let problem = match target {
+        Some((index, name)) => problem.in_target(index, name),
+        None => problem,
+    };
+    Problem {
+        line: position.map(|(line, _)| line),
+        column: position.map(|(_, column)| column),
+        ..problem
+    }
+}
+
+fn capitalized(message: &str) -> String {
+    let mut chars = message.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"[
+  {
+    "name": "google",
+    "command": "https://www.google.com/search?q=",
+    "parser": "GOOGLE"
+  },
+  {
+    "name": "wiki",
+    "parser": {
+      "JSONP": { "callback": "cb", "parser": "BING" }
+    },
+    "http": { "proxy": "nope" }
+  }
+]"#;
+
+    #[test]
+    fn test_scan() {
+        let elements = Scanner::scan(JSON);
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].name.as_deref(), Some("google"));
+        assert_eq!(elements[0].start, Position { line: 2, column: 3 });
+        assert_eq!(elements[1].name.as_deref(), Some("wiki"));
+        assert_eq!(
+            elements[1].locate("parser.JSONP.parser"),
+            Position {
+                line: 10,
+                column: 36
+            }
+        );
+        assert_eq!(
+            elements[1].locate("http.proxy"),
+            Position {
+                line: 12,
+                column: 15
+            }
+        );
+        assert_eq!(
+            elements[1].locate("min_length"),
+            Position { line: 7, column: 3 }
+        );
+    }
+
+    #[test]
+    fn test_locate() {
+        let problems = locate(
+            JSON,
+            vec![Problem::new("proxy", "Invalid proxy", None)
+                .under("http")
+                .in_target(1, "wiki")],
+        );
+        assert_eq!(problems[0].line(), Some(12));
+        assert_eq!(
+            problems[0].to_string(),
+            "line 12, column 15: target `wiki`, field `http.proxy`: Invalid proxy"
+        );
+    }
+
+    #[test]
+    fn test_deserialization() {
+        #[derive(serde::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Target {
+            name: String,
+            command: String,
+        }
+
+        let error = serde_json::from_str::<Vec<Target>>(JSON).unwrap_err();
+        let problem = deserialization(JSON, &error);
+        assert_eq!(problem.target(), Some("wiki"));
+        assert_eq!(problem.field(), "command");
+        assert_eq!(problem.fix(), Some("add `command` to the target"));
+
+        let json = r#"[{"name": "google", "command": 12}]"#;
+        let error = serde_json::from_str::<Vec<Target>>(json).unwrap_err();
+        let problem = deserialization(json, &error);
+        assert_eq!(problem.target(), Some("google"));
+        assert_eq!(problem.field(), "command");
+        assert!(problem.message().starts_with("Invalid type: integer `12`"));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let json = "[\n  {\"name\": \"google\",\n  \"command\" \"x\"}\n]";
+        let error = serde_json::from_str::<serde_json::Value>(json).unwrap_err();
+        let problem = deserialization(json, &error);
+        assert_eq!(problem.line(), Some(3));
+        assert_eq!(problem.target(), Some("google"));
+    }
+
+    #[test]
+    fn test_url() {
+        assert!(url("command", "https://example.com/?q=").is_none());
+        assert!(url("command", "HTTP://example.com/?q=").is_none());
+        assert!(url("command", "ftp://example.com/")
+            .unwrap()
+            .message()
+            .contains("`ftp`"));
+        assert!(url("command", "example.com").is_some());
+    }
+}
@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+
+use super::error;
+use super::executors;
+use super::lenient;
+use super::suggestion::{Origin, Suggestion};
+use super::validation;
+use super::Result;
+
+const MAX_MATCHES: usize = 100;
+
+static INDEXES: std::sync::OnceLock<
+    std::sync::Mutex<std::collections::HashMap<std::path::PathBuf, std::sync::Arc<Index>>>,
+> = std::sync::OnceLock::new();
+
+/// Format of the file backing a [`WordList`](struct.WordList.html)
+#[derive(
+    Serialize, Deserialize, schemars::JsonSchema, Copy, Clone, PartialEq, Eq, Debug, Default,
+)]
+#[serde(remote = "Self", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Format {
+    /// One entry per line. Blank lines are ignored
+    #[default]
+    Lines,
+    /// A JSON array of strings
+    Json,
+}
+
+lenient::lenient_enum!(Format);
+
+/// A fixed vocabulary, read from a local file, that is fuzzy matched against the query
+///
+/// The file is indexed the first time it is used and the index is kept in the
+/// [`cache`](../cache/index.html) until the file changes, so that large lists are matched quickly
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub struct WordList {
+    /// Path to the file. Relative paths are relative to the configuration directory
+    path: String,
+    /// Format of the file
+    #[serde(default)]
+    format: Format,
+}
+
+/// Identifies a version of the file an [`Index`](struct.Index.html) was built from
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+struct Stamp {
+    seconds: u64,
+    nanos: u32,
+    len: u64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Index {
+    source: std::path::PathBuf,
+    stamp: Stamp,
+    format: Format,
+    entries: Vec<String>,
+    masks: Vec<u64>,
+}
+
+/// Summarizes which characters appear in `text`, ignoring case and whitespace
+///
+/// An entry can only match a query if its mask contains every bit of the query mask
+fn mask(text: &str) -> u64 {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .fold(0, |mask, c| mask | 1 << (u32::from(c) % 64))
+}
+
+fn stamp(path: &std::path::Path) -> Result<Stamp> {
+    let metadata = std::fs::metadata(path).map_err(|e| error::Error::Read(path.into(), e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    Ok(Stamp {
+        seconds: modified.as_secs(),
+        nanos: modified.subsec_nanos(),
+        len: metadata.len(),
+    })
+}
+
+impl Index {
+    fn build(source: &std::path::Path, stamp: Stamp, format: Format) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(source).map_err(|e| error::Error::Read(source.into(), e))?;
+        let entries = match format {
+            Format::Lines => contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+            Format::Json => serde_json::from_str::<Vec<String>>(&contents)
+                .map_err(error::Parse::from)
+                .map_err(error::Error::Parse)?,
+        };
+        Ok(Self {
+            source: source.into(),
+            stamp,
+            format,
+            masks: entries.iter().map(|entry| mask(entry)).collect(),
+            entries,
+        })
+    }
+
+    /// Returns the index for `source`, reusing the one in memory or at `cached` if still current
+    ///
+    /// Otherwise, the index is rebuilt and stored at `cached`
+    fn load(
+        source: &std::path::Path,
+        cached: &std::path::Path,
+        format: Format,
+    ) -> Result<std::sync::Arc<Self>> {
+        let stamp = stamp(source)?;
+        let current =
+            |index: &Self| index.source == source && index.stamp == stamp && index.format == format;
+
+        let mut indexes = INDEXES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(index) = indexes.get(cached).filter(|index| current(index)) {
+            return Ok(index.clone());
+        }
+
+        let stored = std::fs::read(cached)
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<Self>(&bytes).ok())
+            .filter(|index| current(index));
+        let index = if let Some(index) = stored {
+            index
+        } else {
+            let index = Self::build(source, stamp, format)?;
+            // Failing to store the index only means it is rebuilt next time
+            write(cached, &index).ok();
+            index
+        };
+
+        let index = std::sync::Arc::new(index);
+        indexes.insert(cached.into(), index.clone());
+        Ok(index)
+    }
+
+    fn search(&self, query: &str, count: usize) -> Vec<Suggestion> {
+        use fuzzy_matcher::FuzzyMatcher;
+
+        let fuzzy = fuzzy_matcher::skim::SkimMatcherV2::default();
+        let wanted = mask(query);
+        let mut matches = self
+            .entries
+            .iter()
+            .zip(&self.masks)
+            .filter(|(_, mask)| *mask & wanted == wanted)
+            .filter_map(|(entry, _)| Some((fuzzy.fuzzy_match(entry, query)?, entry)))
+            .collect::<Vec<_>>();
+
+        // Stable, so entries that match equally well keep the order of the file
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches
+            .into_iter()
+            .take(count)
+            .map(|(score, entry)| Suggestion::new(entry.as_str(), Origin::Remote).with_score(score))
+            .collect()
+    }
+}
+
+fn write(path: &std::path::Path, index: &Index) -> Result {
+    let bytes = bincode::serialize(index)
+        .map_err(error::Serialize::from)
+        .map_err(error::Error::Serialize)?;
+    super::cache::store(path, &bytes)
+}
+
+impl WordList {
+    /// Returns the problems with this word list, such as an empty path
+    pub(crate) fn problems(&self) -> Vec<validation::Problem> {
+        if self.path.trim().is_empty() {
+            vec![validation::Problem::new(
+                "path",
+                "Empty path",
+                Some(String::from("set `path` to the file with the entries")),
+            )]
+        } else {
+            vec![]
+        }
+    }
+
+    fn source(&self) -> Result<std::path::PathBuf> {
+        let path = std::path::Path::new(&self.path);
+        if path.is_absolute() {
+            Ok(path.into())
+        } else {
+            executors::default_path().map(|config| config.join(path))
+        }
+    }
+
+    /// Returns the entries of the list that best match `query`, best first
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Name of the target, identifying the index
+    /// * `query` - Query string to to get suggestions for
+    pub(crate) fn suggestions(&self, target: &str, query: &str) -> Result<Vec<Suggestion>> {
+        let index = Index::load(
+            &self.source()?,
+            &super::cache::index_path(target)?,
+            self.format,
+        )?;
+        Ok(index.search(query, MAX_MATCHES))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory(name: &str) -> std::path::PathBuf {
+        crate::testing::directory(&format!("wordlist_{name}"))
+    }
+
+    fn texts(suggestions: &[Suggestion]) -> Vec<&str> {
+        suggestions.iter().map(Suggestion::text).collect()
+    }
+
+    #[test]
+    fn test_mask_ignores_case_and_whitespace() {
+        assert_eq!(mask("Ab c"), mask("cba"));
+        assert_eq!(mask("ab") & mask("abc"), mask("ab"));
+        assert_ne!(mask("abd") & mask("abc"), mask("abd"));
+    }
+
+    #[test]
+    fn test_search() {
+        let directory = directory("search");
+        let source = directory.join("crates");
+        std::fs::write(&source, "serde\n\nserde_json\ntokio\n  regex  \n").unwrap();
+
+        let index = Index::load(&source, &directory.join("index"), Format::Lines).unwrap();
+        assert_eq!(index.entries, ["serde", "serde_json", "tokio", "regex"]);
+        assert_eq!(texts(&index.search("serde", 10)), ["serde", "serde_json"]);
+        assert_eq!(texts(&index.search("sjsn", 10)), ["serde_json"]);
+        assert_eq!(texts(&index.search("serde", 1)), ["serde"]);
+        assert!(index.search("xyz", 10).is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_json_format() {
+        let directory = directory("json");
+        let source = directory.join("codes.json");
+        std::fs::write(&source, r#"["E0382", "E0499", "E0502"]"#).unwrap();
+
+        let index = Index::load(&source, &directory.join("index"), Format::Json).unwrap();
+        assert_eq!(texts(&index.search("e049", 10)), ["E0499"]);
+
+        std::fs::write(&source, "E0382").unwrap();
+        let other = directory.join("other");
+        assert!(matches!(
+            Index::load(&source, &other, Format::Json),
+            Err(error::Error::Parse(error::Parse::Json(_)))
+        ));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_index_is_rebuilt_when_source_changes() {
+        let directory = directory("rebuild");
+        let source = directory.join("services");
+        let cached = directory.join("index");
+        std::fs::write(&source, "billing\n").unwrap();
+        Index::load(&source, &cached, Format::Lines).unwrap();
+
+        let stored: Index = bincode::deserialize(&std::fs::read(&cached).unwrap()).unwrap();
+        assert_eq!(stored.entries, ["billing"]);
+
+        std::fs::write(&source, "billing\nbookings\n").unwrap();
+        let index = Index::load(&source, &cached, Format::Lines).unwrap();
+        assert_eq!(index.entries, ["billing", "bookings"]);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_index_is_rebuilt_when_format_changes() {
+        let directory = directory("format");
+        let source = directory.join("codes");
+        let cached = directory.join("index");
+        std::fs::write(&source, "[\"E0382\", \"E0499\"]\n").unwrap();
+
+        let lines = Index::load(&source, &cached, Format::Lines).unwrap();
+        assert_eq!(lines.entries, [r#"["E0382", "E0499"]"#]);
+        let json = Index::load(&source, &cached, Format::Json).unwrap();
+        assert_eq!(json.entries, ["E0382", "E0499"]);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_missing_source() {
+        let directory = directory("missing");
+        assert!(matches!(
+            Index::load(
+                &directory.join("nothing"),
+                &directory.join("index"),
+                Format::Lines
+            ),
+            Err(error::Error::Read(..))
+        ));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}